@@ -0,0 +1,72 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+pub const DEFAULT_YEAR: u32 = 2024;
+
+/// Where the puzzle server is and who we are on it, taken from the
+/// environment so tests can point the runner at a local stub.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub base_url: String,
+    pub year: u32,
+    pub session: Option<String>,
+    pub cache: PathBuf,
+    pub history: PathBuf,
+}
+
+impl Config {
+    /// `AOC_BASE_URL`, `AOC_YEAR`, `AOC_SESSION`, `AOC_CACHE` and
+    /// `AOC_HISTORY`; the session falls back to a `.session` file, the cache
+    /// to `inputs/` and the history to `history/`, all in the workspace root
+    /// and all git-ignored.
+    pub fn from_env(root: &Path) -> Config {
+        let var = |name| std::env::var(name).ok().filter(|x| !x.is_empty());
+        Config {
+            base_url: var("AOC_BASE_URL").unwrap_or(DEFAULT_BASE_URL.to_string()),
+            year: var("AOC_YEAR")
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(DEFAULT_YEAR),
+            session: var("AOC_SESSION").or_else(|| {
+                fs::read_to_string(root.join(".session"))
+                    .ok()
+                    .map(|x| x.trim().to_string())
+                    .filter(|x| !x.is_empty())
+            }),
+            cache: var("AOC_CACHE")
+                .map(PathBuf::from)
+                .unwrap_or(root.join("inputs")),
+            history: var("AOC_HISTORY")
+                .map(PathBuf::from)
+                .unwrap_or(root.join("history")),
+        }
+    }
+
+    pub fn session(&self) -> io::Result<&str> {
+        self.session.as_deref().ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no session token, set AOC_SESSION or write it to .session",
+        ))
+    }
+
+    pub fn day_url(&self, day: u32) -> String {
+        format!(
+            "{}/{}/day/{}",
+            self.base_url.trim_end_matches('/'),
+            self.year,
+            day
+        )
+    }
+
+    /// Each day's input sits alone in its own folder, which the runner uses
+    /// as the solver's working directory, so solvers keep reading a plain
+    /// `input.txt`.
+    pub fn input_dir(&self, day: u32) -> PathBuf {
+        self.cache.join(format!("day{}", day))
+    }
+
+    pub fn input_path(&self, day: u32) -> PathBuf {
+        self.input_dir(day).join("input.txt")
+    }
+}
@@ -0,0 +1,45 @@
+// `aoc new` inserts into this table by editing the source, so keep it
+// sorted with one entry per line.
+pub const DAYS: &[(u32, &str)] = &[
+    (1, "day1"),
+    (2, "day2"),
+    (3, "day3"),
+    (4, "day4"),
+    (5, "day5"),
+    (6, "day6"),
+    (7, "day7"),
+    (8, "day8"),
+    (9, "day9"),
+    (10, "day10"),
+    (11, "day11"),
+];
+
+pub fn package(day: u32) -> Option<&'static str> {
+    DAYS.iter().find(|(d, _)| *d == day).map(|(_, name)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_day_crate_is_registered_test() {
+        let manifest = include_str!("../../Cargo.toml");
+        let members = manifest
+            .lines()
+            .map(|x| x.trim().trim_end_matches(',').trim_matches('"'))
+            .filter(|x| {
+                x.strip_prefix("day")
+                    .is_some_and(|n| n.parse::<u32>().is_ok())
+            })
+            .collect::<Vec<_>>();
+        let registered = DAYS.iter().map(|(_, name)| *name).collect::<Vec<_>>();
+        assert_eq!(members, registered);
+    }
+
+    #[test]
+    fn package_test() {
+        assert_eq!(package(10), Some("day10"));
+        assert_eq!(package(25), None);
+    }
+}
@@ -0,0 +1,40 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::http;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fetched {
+    Downloaded(PathBuf),
+    Cached(PathBuf),
+}
+
+/// Downloads the input for `day` into the cache unless it is already there;
+/// an existing file is never requested again nor overwritten.
+pub fn fetch(config: &Config, day: u32) -> io::Result<Fetched> {
+    let path = config.input_path(day);
+    if path.exists() {
+        return Ok(Fetched::Cached(path));
+    }
+    let url = format!("{}/input", config.day_url(day));
+    let response = http::get(&url, config.session()?)?;
+    if response.status != 200 {
+        return Err(io::Error::other(format!(
+            "{} answered {}: {}",
+            url,
+            response.status,
+            response.body.trim()
+        )));
+    }
+    // A half-written file would be taken for the input next time, so the
+    // body only appears under its real name once it is complete.
+    let dir = config.input_dir(day);
+    fs::create_dir_all(&dir)?;
+    let temp = dir.join(format!("input.txt.{}.part", std::process::id()));
+    let saved = fs::write(&temp, &response.body).and_then(|_| fs::hard_link(&temp, &path));
+    let _ = fs::remove_file(&temp);
+    saved?;
+    Ok(Fetched::Downloaded(path))
+}
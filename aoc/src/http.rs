@@ -0,0 +1,262 @@
+use std::io::{self, prelude::*};
+use std::net::TcpStream;
+use std::process::{Command, Stdio};
+
+const USER_AGENT: &str = "github.com/cobr123/AoC-2024-rust aoc runner";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+pub fn get(url: &str, session: &str) -> io::Result<Response> {
+    request("GET", url, session, None)
+}
+
+pub fn post_form(url: &str, session: &str, form: &[(&str, &str)]) -> io::Result<Response> {
+    let body = form
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, encode(v)))
+        .collect::<Vec<_>>()
+        .join("&");
+    request("POST", url, session, Some(body))
+}
+
+/// Plain `http://` is spoken directly, which is all the test stubs need;
+/// std has no TLS, so `https://` goes through the `curl` binary.
+fn request(method: &str, url: &str, session: &str, body: Option<String>) -> io::Result<Response> {
+    if url.starts_with("https://") {
+        return curl(method, url, session, body);
+    }
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or(invalid(format!("unsupported url {}", url)))?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+    let mut stream = TcpStream::connect(if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    })?;
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nCookie: session={}\r\nConnection: close\r\n",
+        method, path, host, USER_AGENT, session
+    );
+    let body = body.unwrap_or_default();
+    if method == "POST" {
+        head += "Content-Type: application/x-www-form-urlencoded\r\n";
+        head += format!("Content-Length: {}\r\n", body.len()).as_str();
+    }
+    stream.write_all(format!("{}\r\n{}", head, body).as_bytes())?;
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw)?;
+    parse_response(&raw)
+}
+
+fn curl(method: &str, url: &str, session: &str, body: Option<String>) -> io::Result<Response> {
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--request", method])
+        .args(["--user-agent", USER_AGENT])
+        .args(["--config", "-"])
+        .args(["--write-out", "\n%{http_code}"]);
+    if let Some(body) = body {
+        command.args(["--data", &body]);
+    }
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Taking the stdin handle closes it once written, so curl sees the end
+    // of its config.
+    let written = child
+        .stdin
+        .take()
+        .map_or(Ok(()), |mut x| x.write_all(curl_config(session).as_bytes()));
+    let output = child.wait_with_output()?;
+    written?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = text
+        .rsplit_once('\n')
+        .ok_or(invalid("curl printed no status".to_string()))?;
+    let status = status
+        .parse()
+        .map_err(|_| invalid(format!("invalid status {}", status)))?;
+    Ok(Response {
+        status,
+        body: body.to_string(),
+    })
+}
+
+/// The session goes to curl as a config file on stdin, since anything on
+/// its command line can be read by every user through `ps`.
+fn curl_config(session: &str) -> String {
+    let mut value = String::new();
+    for c in format!("session={}", session).chars() {
+        match c {
+            '\\' => value += "\\\\",
+            '"' => value += "\\\"",
+            '\n' => value += "\\n",
+            '\r' => value += "\\r",
+            '\t' => value += "\\t",
+            _ => value.push(c),
+        }
+    }
+    format!("cookie = \"{}\"\n", value)
+}
+
+fn parse_response(raw: &[u8]) -> io::Result<Response> {
+    let split = raw
+        .windows(4)
+        .position(|x| x == b"\r\n\r\n")
+        .ok_or(invalid("truncated response".to_string()))?;
+    let head = String::from_utf8_lossy(&raw[..split]);
+    let mut lines = head.lines();
+    let status = lines
+        .next()
+        .and_then(|x| x.split_whitespace().nth(1))
+        .and_then(|x| x.parse().ok())
+        .ok_or(invalid("invalid status line".to_string()))?;
+    let headers = lines
+        .filter_map(|x| x.split_once(':'))
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.trim()))
+        .collect::<Vec<_>>();
+    let header = |name: &str| headers.iter().find(|(x, _)| x == name).map(|(_, v)| *v);
+    let body = &raw[split + 4..];
+    let body = if header("transfer-encoding") == Some("chunked") {
+        dechunk(body)?
+    } else if let Some(length) = header("content-length") {
+        let length = length
+            .parse()
+            .map_err(|_| invalid(format!("invalid content length {}", length)))?;
+        body.get(..length)
+            .ok_or(invalid("truncated response".to_string()))?
+            .to_vec()
+    } else {
+        body.to_vec()
+    };
+    Ok(Response {
+        status,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+fn dechunk(mut raw: &[u8]) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let eol = raw
+            .windows(2)
+            .position(|x| x == b"\r\n")
+            .ok_or(invalid("truncated chunk".to_string()))?;
+        let size = String::from_utf8_lossy(&raw[..eol]);
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or("").trim(), 16)
+            .map_err(|_| invalid(format!("invalid chunk size {}", size)))?;
+        if size == 0 {
+            return Ok(body);
+        }
+        let chunk = raw
+            .get(eol + 2..eol + 2 + size)
+            .ok_or(invalid("truncated chunk".to_string()))?;
+        body.extend_from_slice(chunk);
+        raw = raw.get(eol + 4 + size..).unwrap_or(&[]);
+    }
+}
+
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_response_test() {
+        let result = parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\n1 2\n").unwrap();
+        assert_eq!(
+            result,
+            Response {
+                status: 200,
+                body: "1 2\n".to_string()
+            }
+        );
+        let result = parse_response(
+            b"HTTP/1.1 404 Not Found\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(result.status, 404);
+        assert_eq!(result.body, "abcde");
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\n1 2\n").is_err());
+    }
+
+    #[test]
+    fn curl_config_test() {
+        assert_eq!(curl_config("abc123"), "cookie = \"session=abc123\"\n");
+        assert_eq!(
+            curl_config("a\"b\\c\nd"),
+            "cookie = \"session=a\\\"b\\\\c\\nd\"\n"
+        );
+    }
+
+    #[test]
+    fn curl_sends_session_test() {
+        if Command::new("curl").arg("--version").output().is_err() {
+            return;
+        }
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/input", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut head = Vec::new();
+            let mut byte = [0];
+            while !head.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                head.push(byte[0]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .unwrap();
+            String::from_utf8(head).unwrap()
+        });
+
+        let response = curl("GET", &url, "se\"cret", None).unwrap();
+        assert_eq!(
+            response,
+            Response {
+                status: 200,
+                body: "ok".to_string()
+            }
+        );
+        let head = server.join().unwrap();
+        assert!(head
+            .lines()
+            .any(|x| x.eq_ignore_ascii_case("cookie: session=se\"cret")));
+    }
+
+    #[test]
+    fn encode_test() {
+        assert_eq!(encode("1 a&b=c"), "1%20a%26b%3Dc");
+    }
+}
@@ -0,0 +1,17 @@
+use std::path::{Path, PathBuf};
+
+pub mod config;
+pub mod days;
+pub mod fetch;
+pub mod http;
+pub mod scaffold;
+pub mod solver;
+pub mod submit;
+
+/// The workspace this runner was built in.
+pub fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("the runner lives inside the workspace")
+        .to_path_buf()
+}
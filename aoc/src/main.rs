@@ -0,0 +1,57 @@
+use std::io;
+
+use aoc::config::Config;
+use aoc::fetch::{self, Fetched};
+use aoc::submit::{self, Submission};
+use aoc::{scaffold, solver, workspace_root};
+
+fn main() -> io::Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let root = workspace_root();
+    match args.first().map(|x| x.as_str()) {
+        Some("new") => {
+            let dir = scaffold::new_day(&root, parse_day(args.get(1))?)?;
+            println!("created {}", dir.display());
+            Ok(())
+        }
+        Some("fetch") => {
+            let config = Config::from_env(&root);
+            match fetch::fetch(&config, parse_day(args.get(1))?)? {
+                Fetched::Downloaded(path) => println!("downloaded {}", path.display()),
+                Fetched::Cached(path) => println!("already cached {}", path.display()),
+            }
+            Ok(())
+        }
+        Some("submit") => {
+            let config = Config::from_env(&root);
+            let day = parse_day(args.get(1))?;
+            let part = args
+                .get(2)
+                .and_then(|x| x.parse().ok())
+                .filter(|x| (1..=2).contains(x))
+                .ok_or_else(usage)?;
+            let answer = solver::answer(&config, &root, day, part)?;
+            match submit::submit(&config, day, part, &answer)? {
+                Submission::Refused(refusal) => println!("not submitting {}: {}", answer, refusal),
+                Submission::Answered(reply) => println!("submitted {}: {}", answer, reply),
+            }
+            Ok(())
+        }
+        Some("run") => {
+            let config = Config::from_env(&root);
+            solver::run(&config, &root, parse_day(args.get(1))?).map(|out| print!("{}", out))
+        }
+        _ => Err(usage()),
+    }
+}
+
+fn parse_day(arg: Option<&String>) -> io::Result<u32> {
+    arg.and_then(|x| x.parse().ok()).ok_or_else(usage)
+}
+
+fn usage() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "usage: aoc new <day> | aoc fetch <day> | aoc run <day> | aoc submit <day> <part>",
+    )
+}
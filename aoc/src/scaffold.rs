@@ -0,0 +1,186 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const MANIFEST_TEMPLATE: &str = include_str!("../templates/Cargo.toml.tmpl");
+const MAIN_TEMPLATE: &str = include_str!("../templates/main.rs.tmpl");
+const DAYS_TABLE: &str = "aoc/src/days.rs";
+
+/// Creates the `day<N>` crate under the workspace `root` with an empty
+/// `example.txt` and `answers.txt`, and registers it with the workspace and
+/// the runner's dispatch table.
+pub fn new_day(root: &Path, day: u32) -> io::Result<PathBuf> {
+    if !(1..=25).contains(&day) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("day {} is not between 1 and 25", day),
+        ));
+    }
+    let dir = root.join(format!("day{}", day));
+    if dir.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", dir.display()),
+        ));
+    }
+    // edit both registries in memory first, so a malformed one leaves the
+    // tree untouched
+    let manifest_path = root.join("Cargo.toml");
+    let manifest = register_member(&fs::read_to_string(&manifest_path)?, day).map_err(invalid)?;
+    let table_path = root.join(DAYS_TABLE);
+    let table = register_day(&fs::read_to_string(&table_path)?, day).map_err(invalid)?;
+
+    fs::create_dir_all(dir.join("src"))?;
+    fs::write(dir.join("Cargo.toml"), render(MANIFEST_TEMPLATE, day))?;
+    fs::write(dir.join("src").join("main.rs"), render(MAIN_TEMPLATE, day))?;
+    fs::write(dir.join("example.txt"), "")?;
+    fs::write(dir.join("answers.txt"), "")?;
+    fs::write(manifest_path, manifest)?;
+    fs::write(table_path, table)?;
+    Ok(dir)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn render(template: &str, day: u32) -> String {
+    template.replace("{{day}}", &day.to_string())
+}
+
+/// Adds `day<N>` to the workspace members, before the first later day.
+fn register_member(manifest: &str, day: u32) -> Result<String, String> {
+    let entry_day = |line: &str| {
+        line.trim()
+            .trim_end_matches(',')
+            .trim_matches('"')
+            .strip_prefix("day")
+            .and_then(|n| n.parse::<u32>().ok())
+    };
+    insert_entry(
+        manifest,
+        |line| line.trim_start().starts_with("members = ["),
+        "]",
+        entry_day,
+        day,
+        format!("    \"day{}\",", day),
+    )
+}
+
+/// Adds `(N, "day<N>")` to the `DAYS` table, before the first later day.
+fn register_day(table: &str, day: u32) -> Result<String, String> {
+    let entry_day = |line: &str| {
+        line.trim()
+            .strip_prefix('(')
+            .and_then(|x| x.split(',').next())
+            .and_then(|n| n.parse::<u32>().ok())
+    };
+    insert_entry(
+        table,
+        |line| line.contains("DAYS: &[(u32, &str)] = &["),
+        "];",
+        entry_day,
+        day,
+        format!("    ({}, \"day{}\"),", day, day),
+    )
+}
+
+fn insert_entry(
+    text: &str,
+    is_start: impl Fn(&str) -> bool,
+    end: &str,
+    entry_day: impl Fn(&str) -> Option<u32>,
+    day: u32,
+    entry: String,
+) -> Result<String, String> {
+    let mut lines = text.lines().collect::<Vec<_>>();
+    let start = lines
+        .iter()
+        .position(|x| is_start(x))
+        .ok_or("list to register the day in not found")?;
+    let end = start
+        + lines[start..]
+            .iter()
+            .position(|x| x.trim() == end)
+            .ok_or("list to register the day in is not terminated")?;
+    let entries = &lines[start + 1..end];
+    if entries.iter().any(|x| entry_day(x) == Some(day)) {
+        return Err(format!("day {} is already registered", day));
+    }
+    let at = entries
+        .iter()
+        .position(|x| entry_day(x).is_some_and(|d| d > day))
+        .map_or(end, |i| start + 1 + i);
+    lines.insert(at, &entry);
+    Ok(lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = "[workspace]
+resolver = \"2\"
+members = [
+    \"aoc\",
+    \"day1\",
+    \"day3\",
+    \"day10\",
+]
+";
+
+    const TABLE: &str = "pub const DAYS: &[(u32, &str)] = &[
+    (1, \"day1\"),
+    (3, \"day3\"),
+    (10, \"day10\"),
+];
+";
+
+    #[test]
+    fn register_member_test() {
+        let result = register_member(MANIFEST, 2).unwrap();
+        assert!(result.contains("    \"day1\",\n    \"day2\",\n    \"day3\",\n"));
+        let result = register_member(MANIFEST, 12).unwrap();
+        assert!(result.ends_with("    \"day10\",\n    \"day12\",\n]\n"));
+        assert!(register_member(MANIFEST, 3).is_err());
+        assert!(register_member("[workspace]\n", 3).is_err());
+    }
+
+    #[test]
+    fn register_day_test() {
+        let result = register_day(TABLE, 4).unwrap();
+        assert!(result.contains("    (3, \"day3\"),\n    (4, \"day4\"),\n    (10, \"day10\"),\n"));
+        assert!(register_day(TABLE, 10).is_err());
+    }
+
+    #[test]
+    fn new_day_test() {
+        let root = std::env::temp_dir().join(format!("aoc-scaffold-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("aoc/src")).unwrap();
+        fs::write(root.join("Cargo.toml"), MANIFEST).unwrap();
+        fs::write(root.join(DAYS_TABLE), TABLE).unwrap();
+
+        let dir = new_day(&root, 2).unwrap();
+        assert_eq!(dir, root.join("day2"));
+        let manifest = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("name = \"day2\""));
+        let main = fs::read_to_string(dir.join("src/main.rs")).unwrap();
+        assert!(main.contains("placeholder until day 2 is solved"));
+        assert!(main.contains("read_to_string(\"input.txt\")"));
+        assert_eq!(fs::read_to_string(dir.join("example.txt")).unwrap(), "");
+        assert_eq!(fs::read_to_string(dir.join("answers.txt")).unwrap(), "");
+        let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("\"day2\","));
+        let table = fs::read_to_string(root.join(DAYS_TABLE)).unwrap();
+        assert!(table.contains("(2, \"day2\"),"));
+
+        let err = new_day(&root, 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(
+            new_day(&root, 26).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::Config;
+use crate::days;
+
+/// Runs the solver for `day` and returns what it printed. Solvers read
+/// `input.txt` from their working directory, which is the fetched input's
+/// folder once `aoc fetch` has run and the crate folder otherwise.
+pub fn run(config: &Config, root: &Path, day: u32) -> io::Result<String> {
+    let package = days::package(day).ok_or(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("day {} is not registered, see `aoc new {}`", day, day),
+    ))?;
+    let cargo = std::env::var("CARGO").unwrap_or("cargo".to_string());
+    let output = Command::new(cargo)
+        .args(["run", "--release", "--quiet", "--package", package])
+        .arg("--manifest-path")
+        .arg(root.join("Cargo.toml"))
+        .current_dir(working_dir(config, root, day, package))
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} failed: {}",
+            package,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn working_dir(config: &Config, root: &Path, day: u32, package: &str) -> PathBuf {
+    if config.input_path(day).exists() {
+        config.input_dir(day)
+    } else {
+        root.join(package)
+    }
+}
+
+/// The answer to `part`, which solvers print on line `part`.
+pub fn answer(config: &Config, root: &Path, day: u32, part: u32) -> io::Result<String> {
+    let output = run(config, root, day)?;
+    output
+        .lines()
+        .nth(part as usize - 1)
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .ok_or(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("day {} printed no answer for part {}", day, part),
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn working_dir_test() {
+        let dir = std::env::temp_dir().join(format!("aoc-solver-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let config = Config {
+            base_url: String::new(),
+            year: 2024,
+            session: None,
+            cache: dir.join("inputs"),
+            history: dir.join("history"),
+        };
+        let root = dir.join("root");
+        assert_eq!(working_dir(&config, &root, 3, "day3"), root.join("day3"));
+        fs::create_dir_all(config.input_dir(3)).unwrap();
+        fs::write(config.input_path(3), "mul(2,4)").unwrap();
+        assert_eq!(
+            working_dir(&config, &root, 3, "day3"),
+            dir.join("inputs/day3")
+        );
+        assert_eq!(working_dir(&config, &root, 4, "day4"), root.join("day4"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,372 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, prelude::*};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::http;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Right,
+    Wrong,
+    TooHigh,
+    TooLow,
+}
+
+impl Verdict {
+    fn name(self) -> &'static str {
+        match self {
+            Verdict::Right => "right",
+            Verdict::Wrong => "wrong",
+            Verdict::TooHigh => "too-high",
+            Verdict::TooLow => "too-low",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Verdict> {
+        [
+            Verdict::Right,
+            Verdict::Wrong,
+            Verdict::TooHigh,
+            Verdict::TooLow,
+        ]
+        .into_iter()
+        .find(|x| x.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Judged(Verdict),
+    TooRecent,
+    AlreadySolved,
+    Unrecognised(String),
+}
+
+/// What the server made of a submission, and how long it wants us to wait
+/// before the next one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub outcome: Outcome,
+    pub wait: Option<Duration>,
+}
+
+impl Reply {
+    pub fn parse(body: &str) -> Reply {
+        let text = article_text(body);
+        let outcome = if text.contains("That's the right answer") {
+            Outcome::Judged(Verdict::Right)
+        } else if text.contains("That's not the right answer") {
+            Outcome::Judged(if text.contains("your answer is too high") {
+                Verdict::TooHigh
+            } else if text.contains("your answer is too low") {
+                Verdict::TooLow
+            } else {
+                Verdict::Wrong
+            })
+        } else if text.contains("You gave an answer too recently") {
+            Outcome::TooRecent
+        } else if text.contains("Did you already complete it") {
+            Outcome::AlreadySolved
+        } else {
+            Outcome::Unrecognised(text.clone())
+        };
+        Reply {
+            outcome,
+            wait: parse_wait(&text),
+        }
+    }
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.outcome {
+            Outcome::Judged(Verdict::Right) => write!(f, "right answer")?,
+            Outcome::Judged(Verdict::Wrong) => write!(f, "wrong answer")?,
+            Outcome::Judged(Verdict::TooHigh) => write!(f, "wrong answer, too high")?,
+            Outcome::Judged(Verdict::TooLow) => write!(f, "wrong answer, too low")?,
+            Outcome::TooRecent => write!(f, "answered too recently")?,
+            Outcome::AlreadySolved => write!(f, "this part is already solved")?,
+            Outcome::Unrecognised(text) => write!(f, "unrecognised reply: {}", text)?,
+        }
+        match self.wait {
+            Some(wait) => write!(f, ", wait {}s", wait.as_secs()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The message inside `<article>` with the markup stripped.
+fn article_text(body: &str) -> String {
+    let start = body.find("<article>").map_or(0, |i| i + "<article>".len());
+    let end = body[start..]
+        .find("</article>")
+        .map_or(body.len(), |i| start + i);
+    let mut text = String::new();
+    let mut in_tag = false;
+    for ch in body[start..end].chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => (),
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Understands "you have 1m 30s left to wait" after answering too soon, and
+/// "please wait one minute" / "wait 5 minutes" after a wrong answer.
+fn parse_wait(text: &str) -> Option<Duration> {
+    let text = text.to_ascii_lowercase();
+    if let Some(end) = text.find(" left to wait") {
+        let start = text[..end].rfind("you have ")? + "you have ".len();
+        let mut secs = 0;
+        for part in text[start..end].split_whitespace() {
+            let (n, unit) = part.split_at(part.find(|c: char| !c.is_ascii_digit())?);
+            let n = n.parse::<u64>().ok()?;
+            secs += match unit {
+                "h" => n * 3600,
+                "m" => n * 60,
+                "s" => n,
+                _ => return None,
+            };
+        }
+        return Some(Duration::from_secs(secs));
+    }
+    let mut words = text.split_whitespace().skip_while(|x| *x != "wait").skip(1);
+    let n = match words.next()? {
+        "one" | "a" => 1,
+        n => n.parse::<u64>().ok()?,
+    };
+    words
+        .next()?
+        .starts_with("minute")
+        .then(|| Duration::from_secs(n * 60))
+}
+
+/// Answers sent for one part of one day, as recorded in
+/// `<history>/day<N>-part<P>.txt`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    pub entries: Vec<(String, Verdict)>,
+}
+
+impl History {
+    pub fn load(path: &Path) -> io::Result<History> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(History::default()),
+            Err(e) => return Err(e),
+        };
+        let entries = text
+            .lines()
+            .map(|line| {
+                line.rsplit_once('\t')
+                    .and_then(|(answer, verdict)| {
+                        Some((answer.to_string(), Verdict::from_name(verdict)?))
+                    })
+                    .ok_or(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid history line {:?} in {}", line, path.display()),
+                    ))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(History { entries })
+    }
+
+    fn append(path: &Path, answer: &str, verdict: Verdict) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}\t{}", answer, verdict.name())
+    }
+
+    /// Why `answer` is certainly not worth sending, if it is not.
+    pub fn check(&self, answer: &str) -> Option<Refusal> {
+        if let Some((right, _)) = self.entries.iter().find(|(_, v)| *v == Verdict::Right) {
+            return Some(Refusal::AlreadySolved(right.clone()));
+        }
+        if self.entries.iter().any(|(x, _)| x == answer) {
+            return Some(Refusal::KnownWrong);
+        }
+        let value = answer.parse::<i128>().ok()?;
+        let bound = |verdict| {
+            self.entries
+                .iter()
+                .filter(move |(_, v)| *v == verdict)
+                .filter_map(|(x, _)| x.parse::<i128>().ok())
+        };
+        if let Some(high) = bound(Verdict::TooHigh).min().filter(|x| value >= *x) {
+            return Some(Refusal::TooHigh(high));
+        }
+        if let Some(low) = bound(Verdict::TooLow).max().filter(|x| value <= *x) {
+            return Some(Refusal::TooLow(low));
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refusal {
+    AlreadySolved(String),
+    KnownWrong,
+    TooHigh(i128),
+    TooLow(i128),
+    CoolingDown(Duration),
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::AlreadySolved(answer) => write!(f, "already solved with {}", answer),
+            Refusal::KnownWrong => write!(f, "already known to be wrong"),
+            Refusal::TooHigh(high) => write!(f, "{} was already too high", high),
+            Refusal::TooLow(low) => write!(f, "{} was already too low", low),
+            Refusal::CoolingDown(wait) => write!(f, "wait another {}s", wait.as_secs()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Submission {
+    Refused(Refusal),
+    Answered(Reply),
+}
+
+/// Posts `answer` unless the history or a pending cooldown already says how
+/// it would go, then records what the server replied.
+pub fn submit(config: &Config, day: u32, part: u32, answer: &str) -> io::Result<Submission> {
+    if !(1..=2).contains(&part) || answer.is_empty() || answer.contains(char::is_whitespace) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("cannot submit {:?} for part {}", answer, part),
+        ));
+    }
+    let history_path = history_path(config, day, part);
+    if let Some(refusal) = History::load(&history_path)?.check(answer) {
+        return Ok(Submission::Refused(refusal));
+    }
+    let cooldown_path = config.history.join("cooldown");
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    if let Some(until) = read_cooldown(&cooldown_path)?.filter(|x| *x > now) {
+        return Ok(Submission::Refused(Refusal::CoolingDown(until - now)));
+    }
+
+    let url = format!("{}/answer", config.day_url(day));
+    let level = part.to_string();
+    let response = http::post_form(
+        &url,
+        config.session()?,
+        &[("level", &level), ("answer", answer)],
+    )?;
+    if response.status != 200 {
+        return Err(io::Error::other(format!(
+            "{} answered {}: {}",
+            url,
+            response.status,
+            response.body.trim()
+        )));
+    }
+    let reply = Reply::parse(&response.body);
+    if let Outcome::Judged(verdict) = reply.outcome {
+        History::append(&history_path, answer, verdict)?;
+    }
+    if let Some(wait) = reply.wait {
+        fs::create_dir_all(&config.history)?;
+        fs::write(&cooldown_path, (now + wait).as_secs().to_string())?;
+    }
+    Ok(Submission::Answered(reply))
+}
+
+pub fn history_path(config: &Config, day: u32, part: u32) -> PathBuf {
+    config.history.join(format!("day{}-part{}.txt", day, part))
+}
+
+fn read_cooldown(path: &Path) -> io::Result<Option<Duration>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text.trim().parse().ok().map(Duration::from_secs)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(message: &str) -> String {
+        format!(
+            "<html><body><main>\n<article><p>{}</p></article>\n</main></body></html>",
+            message
+        )
+    }
+
+    #[test]
+    fn parse_reply_test() {
+        let reply = Reply::parse(&page(
+            "That's the right answer!  You are <span class=\"day-success\">one gold star</span> closer.",
+        ));
+        assert_eq!(reply.outcome, Outcome::Judged(Verdict::Right));
+        assert_eq!(reply.wait, None);
+
+        let reply = Reply::parse(&page(
+            "That's not the right answer; your answer is too high.  If you're stuck, make sure you're using the full input data.  Please wait one minute before trying again.",
+        ));
+        assert_eq!(reply.outcome, Outcome::Judged(Verdict::TooHigh));
+        assert_eq!(reply.wait, Some(Duration::from_secs(60)));
+
+        let reply = Reply::parse(&page(
+            "That's not the right answer; your answer is too low.  Please wait 5 minutes before trying again.",
+        ));
+        assert_eq!(reply.outcome, Outcome::Judged(Verdict::TooLow));
+        assert_eq!(reply.wait, Some(Duration::from_secs(300)));
+
+        let reply = Reply::parse(&page(
+            "You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 1m 30s left to wait.",
+        ));
+        assert_eq!(reply.outcome, Outcome::TooRecent);
+        assert_eq!(reply.wait, Some(Duration::from_secs(90)));
+
+        let reply = Reply::parse(&page(
+            "You don't seem to be solving the right level.  Did you already complete it?",
+        ));
+        assert_eq!(reply.outcome, Outcome::AlreadySolved);
+
+        let reply = Reply::parse("<p>Gateway <b>timeout</b></p>");
+        assert_eq!(
+            reply.outcome,
+            Outcome::Unrecognised("Gateway timeout".to_string())
+        );
+    }
+
+    #[test]
+    fn history_check_test() {
+        let history = History {
+            entries: vec![
+                ("100".to_string(), Verdict::TooHigh),
+                ("20".to_string(), Verdict::TooLow),
+                ("50".to_string(), Verdict::Wrong),
+                ("80".to_string(), Verdict::TooHigh),
+            ],
+        };
+        assert_eq!(history.check("50"), Some(Refusal::KnownWrong));
+        assert_eq!(history.check("90"), Some(Refusal::TooHigh(80)));
+        assert_eq!(history.check("20"), Some(Refusal::KnownWrong));
+        assert_eq!(history.check("7"), Some(Refusal::TooLow(20)));
+        assert_eq!(history.check("21"), None);
+        assert_eq!(history.check("abc"), None);
+
+        let mut solved = history.clone();
+        solved.entries.push(("42".to_string(), Verdict::Right));
+        assert_eq!(
+            solved.check("43"),
+            Some(Refusal::AlreadySolved("42".to_string()))
+        );
+    }
+}
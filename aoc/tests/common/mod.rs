@@ -0,0 +1,97 @@
+use std::io::{prelude::*, BufReader};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recorded {
+    pub method: String,
+    pub path: String,
+    pub cookie: Option<String>,
+    pub body: String,
+}
+
+pub struct StubServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl StubServer {
+    /// Answers each incoming request with the next canned `(status, body)`.
+    pub fn start(responses: &[(u16, &str)]) -> StubServer {
+        let responses = responses
+            .iter()
+            .map(|(status, body)| {
+                format!(
+                    "HTTP/1.1 {} Stub\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+            })
+            .collect::<Vec<_>>();
+        StubServer::start_raw(&responses.iter().map(|x| x.as_str()).collect::<Vec<_>>())
+    }
+
+    /// Writes each response verbatim, for replies a real server could send
+    /// but `start` would not, like a connection cut mid-body.
+    pub fn start_raw(responses: &[&str]) -> StubServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        let responses = responses.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        thread::spawn(move || {
+            for (response, stream) in responses.into_iter().zip(listener.incoming()) {
+                let mut stream = stream.unwrap();
+                let request = read_request(&mut BufReader::new(&stream));
+                recorded.lock().unwrap().push(request);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        StubServer { base_url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request<R: BufRead>(reader: &mut R) -> Recorded {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap().to_string();
+    let path = parts.next().unwrap().to_string();
+    let mut cookie = None;
+    let mut length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        let Some((name, value)) = line.trim_end().split_once(": ") else {
+            break;
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "cookie" => cookie = Some(value.to_string()),
+            "content-length" => length = value.parse().unwrap(),
+            _ => (),
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    Recorded {
+        method,
+        path,
+        cookie,
+        body: String::from_utf8(body).unwrap(),
+    }
+}
+
+/// A fresh directory per test, so tests can run in parallel.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aoc-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use aoc::config::Config;
+use aoc::fetch::{fetch, Fetched};
+use common::{temp_dir, StubServer};
+
+fn config(server: &StubServer, cache: &Path) -> Config {
+    Config {
+        base_url: server.base_url.clone(),
+        year: 2024,
+        session: Some("secret".to_string()),
+        cache: cache.join("inputs"),
+        history: cache.join("history"),
+    }
+}
+
+#[test]
+fn fetch_downloads_once_test() {
+    let server = StubServer::start(&[(200, "3   4\n4   3\n")]);
+    let dir = temp_dir("fetch-once");
+    let config = config(&server, &dir);
+    let path = dir.join("inputs").join("day1").join("input.txt");
+
+    assert_eq!(
+        fetch(&config, 1).unwrap(),
+        Fetched::Downloaded(path.clone())
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), "3   4\n4   3\n");
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "GET");
+    assert_eq!(requests[0].path, "/2024/day/1/input");
+    assert_eq!(requests[0].cookie.as_deref(), Some("session=secret"));
+
+    assert_eq!(fetch(&config, 1).unwrap(), Fetched::Cached(path));
+    assert_eq!(server.requests().len(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fetch_never_overwrites_test() {
+    let server = StubServer::start(&[(200, "theirs")]);
+    let dir = temp_dir("fetch-keep");
+    let config = config(&server, &dir);
+    fs::create_dir_all(config.input_dir(5)).unwrap();
+    fs::write(config.input_path(5), "mine").unwrap();
+
+    assert_eq!(
+        fetch(&config, 5).unwrap(),
+        Fetched::Cached(config.input_path(5))
+    );
+    assert_eq!(fs::read_to_string(config.input_path(5)).unwrap(), "mine");
+    assert!(server.requests().is_empty());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fetch_error_status_test() {
+    let server = StubServer::start(&[(
+        404,
+        "Please don't repeatedly request this endpoint before it unlocks!",
+    )]);
+    let dir = temp_dir("fetch-locked");
+    let config = config(&server, &dir);
+
+    let err = fetch(&config, 25).unwrap_err();
+    assert!(err.to_string().contains("404"));
+    assert!(!config.input_path(25).exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fetch_truncated_download_test() {
+    let server = StubServer::start_raw(&[
+        "HTTP/1.1 200 OK\r\nContent-Length: 12\r\nConnection: close\r\n\r\n3   4\n",
+        "HTTP/1.1 200 OK\r\nContent-Length: 12\r\nConnection: close\r\n\r\n3   4\n4   3\n",
+    ]);
+    let dir = temp_dir("fetch-truncated");
+    let config = config(&server, &dir);
+
+    assert!(fetch(&config, 3).is_err());
+    assert!(!config.input_path(3).exists());
+
+    assert_eq!(
+        fetch(&config, 3).unwrap(),
+        Fetched::Downloaded(config.input_path(3))
+    );
+    assert_eq!(
+        fs::read_to_string(config.input_path(3)).unwrap(),
+        "3   4\n4   3\n"
+    );
+    assert_eq!(fs::read_dir(config.input_dir(3)).unwrap().count(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fetch_without_session_test() {
+    let server = StubServer::start(&[]);
+    let dir = temp_dir("fetch-anonymous");
+    let config = Config {
+        session: None,
+        ..config(&server, &dir)
+    };
+
+    assert_eq!(
+        fetch(&config, 2).unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+    assert!(server.requests().is_empty());
+    fs::remove_dir_all(dir).unwrap();
+}
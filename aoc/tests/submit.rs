@@ -0,0 +1,123 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use aoc::config::Config;
+use aoc::submit::{history_path, submit, Outcome, Refusal, Reply, Submission, Verdict};
+use common::{temp_dir, StubServer};
+
+const TOO_HIGH: &str = "<main><article><p>That's not the right answer; your answer is too high.  \
+    If you're stuck, make sure you're using the full input data.  \
+    Please wait one minute before trying again.</p></article></main>";
+const RIGHT: &str = "<main><article><p>That's the right answer!  \
+    You are <span class=\"day-success\">one gold star</span> closer.</p></article></main>";
+const TOO_RECENT: &str = "<main><article><p>You gave an answer too recently; you have to wait \
+    after submitting an answer before trying again.  You have 45s left to wait.</p></article></main>";
+
+fn config(server: &StubServer, dir: &Path) -> Config {
+    Config {
+        base_url: server.base_url.clone(),
+        year: 2024,
+        session: Some("secret".to_string()),
+        cache: dir.join("inputs"),
+        history: dir.join("history"),
+    }
+}
+
+#[test]
+fn submit_wrong_answer_test() {
+    let server = StubServer::start(&[(200, TOO_HIGH)]);
+    let dir = temp_dir("submit-wrong");
+    let config = config(&server, &dir);
+
+    assert_eq!(
+        submit(&config, 1, 1, "100").unwrap(),
+        Submission::Answered(Reply {
+            outcome: Outcome::Judged(Verdict::TooHigh),
+            wait: Some(Duration::from_secs(60)),
+        })
+    );
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].path, "/2024/day/1/answer");
+    assert_eq!(requests[0].cookie.as_deref(), Some("session=secret"));
+    assert_eq!(requests[0].body, "level=1&answer=100");
+    assert_eq!(
+        fs::read_to_string(history_path(&config, 1, 1)).unwrap(),
+        "100\ttoo-high\n"
+    );
+
+    // both are settled locally, without asking the server again
+    assert_eq!(
+        submit(&config, 1, 1, "150").unwrap(),
+        Submission::Refused(Refusal::TooHigh(100))
+    );
+    assert!(matches!(
+        submit(&config, 1, 1, "90").unwrap(),
+        Submission::Refused(Refusal::CoolingDown(_))
+    ));
+    assert_eq!(server.requests().len(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn submit_right_answer_test() {
+    let server = StubServer::start(&[(200, RIGHT)]);
+    let dir = temp_dir("submit-right");
+    let config = config(&server, &dir);
+
+    assert_eq!(
+        submit(&config, 3, 2, "48").unwrap(),
+        Submission::Answered(Reply {
+            outcome: Outcome::Judged(Verdict::Right),
+            wait: None,
+        })
+    );
+    assert_eq!(server.requests()[0].body, "level=2&answer=48");
+    assert_eq!(
+        submit(&config, 3, 2, "49").unwrap(),
+        Submission::Refused(Refusal::AlreadySolved("48".to_string()))
+    );
+    assert_eq!(server.requests().len(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn submit_too_recently_test() {
+    let server = StubServer::start(&[(200, TOO_RECENT)]);
+    let dir = temp_dir("submit-recent");
+    let config = config(&server, &dir);
+
+    assert_eq!(
+        submit(&config, 2, 1, "2").unwrap(),
+        Submission::Answered(Reply {
+            outcome: Outcome::TooRecent,
+            wait: Some(Duration::from_secs(45)),
+        })
+    );
+    assert!(!history_path(&config, 2, 1).exists());
+    match submit(&config, 2, 1, "2").unwrap() {
+        Submission::Refused(Refusal::CoolingDown(wait)) => {
+            assert!(wait <= Duration::from_secs(45))
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(server.requests().len(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn submit_server_error_test() {
+    let server = StubServer::start(&[(500, "Internal Server Error")]);
+    let dir = temp_dir("submit-error");
+    let config = config(&server, &dir);
+
+    let err = submit(&config, 4, 1, "18").unwrap_err();
+    assert!(err.to_string().contains("500"));
+    assert!(!history_path(&config, 4, 1).exists());
+    assert!(submit(&config, 4, 3, "18").is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,144 @@
+pub struct Pairs<'a, T> {
+    items: &'a [T],
+    i: usize,
+    j: usize,
+}
+
+pub fn pairs<T>(items: &[T]) -> Pairs<'_, T> {
+    Pairs { items, i: 0, j: 1 }
+}
+
+impl<'a, T> Iterator for Pairs<'a, T> {
+    type Item = (&'a T, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.j >= self.items.len() {
+            self.i += 1;
+            self.j = self.i + 1;
+        }
+        if self.j >= self.items.len() {
+            return None;
+        }
+        let pair = (&self.items[self.i], &self.items[self.j]);
+        self.j += 1;
+        Some(pair)
+    }
+}
+
+pub struct Combinations<'a, T> {
+    items: &'a [T],
+    indices: Vec<usize>,
+    chosen: Vec<&'a T>,
+    started: bool,
+}
+
+pub fn combinations<T>(items: &[T], k: usize) -> Combinations<'_, T> {
+    Combinations {
+        items,
+        indices: (0..k).collect(),
+        chosen: Vec::with_capacity(k),
+        started: false,
+    }
+}
+
+impl<'a, T> Combinations<'a, T> {
+    pub fn next_combination(&mut self) -> Option<&[&'a T]> {
+        let (n, k) = (self.items.len(), self.indices.len());
+        if k > n {
+            return None;
+        }
+        if self.started {
+            let i = (0..k).rev().find(|&i| self.indices[i] != i + n - k)?;
+            self.indices[i] += 1;
+            for j in i + 1..k {
+                self.indices[j] = self.indices[j - 1] + 1;
+            }
+        }
+        self.started = true;
+        self.chosen.clear();
+        self.chosen
+            .extend(self.indices.iter().map(|&i| &self.items[i]));
+        Some(&self.chosen)
+    }
+}
+
+pub struct Permutations<'a, T> {
+    items: &'a [T],
+    indices: Vec<usize>,
+    ordered: Vec<&'a T>,
+    started: bool,
+}
+
+pub fn permutations<T>(items: &[T]) -> Permutations<'_, T> {
+    Permutations {
+        items,
+        indices: (0..items.len()).collect(),
+        ordered: Vec::with_capacity(items.len()),
+        started: false,
+    }
+}
+
+impl<'a, T> Permutations<'a, T> {
+    pub fn next_permutation(&mut self) -> Option<&[&'a T]> {
+        if self.started {
+            // next lexicographic order of the index vector
+            let i = (1..self.indices.len())
+                .rev()
+                .find(|&i| self.indices[i - 1] < self.indices[i])?
+                - 1;
+            let j = (i + 1..self.indices.len())
+                .rev()
+                .find(|&j| self.indices[i] < self.indices[j])
+                .unwrap();
+            self.indices.swap(i, j);
+            self.indices[i + 1..].reverse();
+        }
+        self.started = true;
+        self.ordered.clear();
+        self.ordered
+            .extend(self.indices.iter().map(|&i| &self.items[i]));
+        Some(&self.ordered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_test() {
+        let result = pairs(&[1, 2, 3]).collect::<Vec<_>>();
+        assert_eq!(result, vec![(&1, &2), (&1, &3), (&2, &3)]);
+        assert_eq!(pairs(&[1]).count(), 0);
+        assert_eq!(pairs::<i32>(&[]).count(), 0);
+    }
+
+    #[test]
+    fn combinations_test() {
+        let mut result = Vec::new();
+        let mut combos = combinations(&['a', 'b', 'c', 'd'], 3);
+        while let Some(combo) = combos.next_combination() {
+            result.push(combo.iter().copied().collect::<String>());
+        }
+        assert_eq!(result, vec!["abc", "abd", "acd", "bcd"]);
+
+        let mut combos = combinations(&[1, 2], 0);
+        assert_eq!(combos.next_combination(), Some(&[][..]));
+        assert_eq!(combos.next_combination(), None);
+        assert_eq!(combinations(&[1, 2], 3).next_combination(), None);
+    }
+
+    #[test]
+    fn permutations_test() {
+        let mut result = Vec::new();
+        let mut perms = permutations(&['a', 'b', 'c']);
+        while let Some(perm) = perms.next_permutation() {
+            result.push(perm.iter().copied().collect::<String>());
+        }
+        assert_eq!(result, vec!["abc", "acb", "bac", "bca", "cab", "cba"]);
+
+        let mut perms = permutations::<i32>(&[]);
+        assert_eq!(perms.next_permutation(), Some(&[][..]));
+        assert_eq!(perms.next_permutation(), None);
+    }
+}
@@ -0,0 +1,477 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, prelude::*, BufReader, BufWriter};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pairing {
+    Sorted,
+    Original,
+    Optimal,
+}
+
+/// Totals are kept in 128 bits: a single difference can take all 64 bits
+/// when IDs sit near both ends of `i64`, so even two rows could overflow a
+/// `u64`.
+pub fn distance(left: &[i64], right: &[i64], pairing: Pairing) -> u128 {
+    match pairing {
+        Pairing::Original => pair_distance(left, right),
+        Pairing::Sorted => {
+            let (mut left, mut right) = (left.to_vec(), right.to_vec());
+            left.sort();
+            right.sort();
+            pair_distance(&left, &right)
+        }
+        Pairing::Optimal if left.len() == right.len() => distance(left, right, Pairing::Sorted),
+        Pairing::Optimal => optimal_distance(left, right),
+    }
+}
+
+fn pair_distance(left: &[i64], right: &[i64]) -> u128 {
+    left.iter()
+        .zip(right)
+        .map(|(a, b)| a.abs_diff(*b) as u128)
+        .sum()
+}
+
+fn optimal_distance(left: &[i64], right: &[i64]) -> u128 {
+    let (mut short, mut long) = if left.len() <= right.len() {
+        (left.to_vec(), right.to_vec())
+    } else {
+        (right.to_vec(), left.to_vec())
+    };
+    short.sort();
+    long.sort();
+
+    // best[j] = cost of matching the first i short values within the first j long values
+    let mut best = vec![0u128; long.len() + 1];
+    for i in 1..=short.len() {
+        let mut next = vec![u128::MAX; long.len() + 1];
+        for j in i..=long.len() {
+            let take = best[j - 1].saturating_add(short[i - 1].abs_diff(long[j - 1]) as u128);
+            next[j] = next[j - 1].min(take);
+        }
+        best = next;
+    }
+    best[long.len()]
+}
+
+pub fn similarity<F>(left: &[i64], right: &[i64], weight: F) -> i128
+where
+    F: Fn(i64, usize) -> i128,
+{
+    let counts = count(right);
+    left.iter()
+        .map(|a| weight(*a, counts.get(a).copied().unwrap_or(0)))
+        .sum()
+}
+
+pub fn value_times_count(value: i64, count: usize) -> i128 {
+    value as i128 * count as i128
+}
+
+pub fn jaccard(left: &[i64], right: &[i64]) -> f64 {
+    let left = left.iter().collect::<BTreeSet<_>>();
+    let right = right.iter().collect::<BTreeSet<_>>();
+    let union = left.union(&right).count();
+    if union == 0 {
+        return 1.0;
+    }
+    left.intersection(&right).count() as f64 / union as f64
+}
+
+pub fn multiset_overlap(left: &[i64], right: &[i64]) -> usize {
+    let right = count(right);
+    count(left)
+        .iter()
+        .map(|(a, n)| *n.min(right.get(a).unwrap_or(&0)))
+        .sum()
+}
+
+pub fn spearman(left: &[i64], right: &[i64]) -> Option<f64> {
+    let n = left.len().min(right.len());
+    if n < 2 {
+        return None;
+    }
+    let (rank_l, rank_r) = (ranks(&left[..n]), ranks(&right[..n]));
+    let mean = (n as f64 + 1.0) / 2.0;
+    let (mut cov, mut var_l, mut var_r) = (0.0, 0.0, 0.0);
+    for i in 0..n {
+        let (dl, dr) = (rank_l[i] - mean, rank_r[i] - mean);
+        cov += dl * dr;
+        var_l += dl * dl;
+        var_r += dr * dr;
+    }
+    if var_l == 0.0 || var_r == 0.0 {
+        return None;
+    }
+    Some(cov / (var_l * var_r).sqrt())
+}
+
+fn ranks(values: &[i64]) -> Vec<f64> {
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| values[i]);
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+        // tied values share the average of their 1-based positions
+        let rank = (start + end) as f64 / 2.0 + 1.0;
+        for &i in &order[start..=end] {
+            ranks[i] = rank;
+        }
+        start = end + 1;
+    }
+    ranks
+}
+
+fn count(values: &[i64]) -> BTreeMap<i64, usize> {
+    let mut counts = BTreeMap::new();
+    for v in values {
+        *counts.entry(*v).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ColumnCounts {
+    pub left: BTreeMap<i64, usize>,
+    pub right: BTreeMap<i64, usize>,
+}
+
+impl ColumnCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, a: i64, b: i64) {
+        *self.left.entry(a).or_insert(0) += 1;
+        *self.right.entry(b).or_insert(0) += 1;
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.left.len() + self.right.len()
+    }
+
+    pub fn similarity<F>(&self, weight: F) -> i128
+    where
+        F: Fn(i64, usize) -> i128,
+    {
+        self.left
+            .iter()
+            .map(|(a, n)| weight(*a, self.right.get(a).copied().unwrap_or(0)) * *n as i128)
+            .sum()
+    }
+
+    pub fn sorted_distance(&self) -> u128 {
+        let mut left = self.left.iter().map(|(v, n)| (*v, *n));
+        let mut right = self.right.iter().map(|(v, n)| (*v, *n));
+        let (mut l, mut r) = (left.next(), right.next());
+        let mut sum = 0;
+        while let (Some((a, n_a)), Some((b, n_b))) = (l, r) {
+            let n = n_a.min(n_b);
+            sum += a.abs_diff(b) as u128 * n as u128;
+            l = if n_a == n {
+                left.next()
+            } else {
+                Some((a, n_a - n))
+            };
+            r = if n_b == n {
+                right.next()
+            } else {
+                Some((b, n_b - n))
+            };
+        }
+        sum
+    }
+}
+
+/// At most this many runs are read at once per column, so the number of
+/// open files stays bounded however many runs the input produces.
+pub const MAX_FAN_IN: usize = 64;
+
+pub fn external_sorted_distance<I>(pairs: I, run_len: usize) -> io::Result<u128>
+where
+    I: IntoIterator<Item = io::Result<(i64, i64)>>,
+{
+    merged_distance(pairs, run_len, MAX_FAN_IN)
+}
+
+fn merged_distance<I>(pairs: I, run_len: usize, fan_in: usize) -> io::Result<u128>
+where
+    I: IntoIterator<Item = io::Result<(i64, i64)>>,
+{
+    let (mut runs_l, mut runs_r) = (Vec::new(), Vec::new());
+    let (mut buf_l, mut buf_r) = (Vec::with_capacity(run_len), Vec::with_capacity(run_len));
+    for pair in pairs {
+        let (a, b) = pair?;
+        buf_l.push(a);
+        buf_r.push(b);
+        if buf_l.len() >= run_len {
+            runs_l.push(Run::write(&mut buf_l)?);
+            runs_r.push(Run::write(&mut buf_r)?);
+        }
+    }
+    if !buf_l.is_empty() {
+        runs_l.push(Run::write(&mut buf_l)?);
+        runs_r.push(Run::write(&mut buf_r)?);
+    }
+
+    let runs_l = reduce_runs(runs_l, fan_in)?;
+    let runs_r = reduce_runs(runs_r, fan_in)?;
+    let (mut left, mut right) = (Merge::new(&runs_l)?, Merge::new(&runs_r)?);
+    let mut sum = 0;
+    while let (Some(a), Some(b)) = (left.next()?, right.next()?) {
+        sum += a.abs_diff(b) as u128;
+    }
+    Ok(sum)
+}
+
+/// Merges groups of `fan_in` runs into longer runs until a single final
+/// merge can read all of them at once.
+fn reduce_runs(mut runs: Vec<Run>, fan_in: usize) -> io::Result<Vec<Run>> {
+    let fan_in = fan_in.max(2);
+    while runs.len() > fan_in {
+        runs = runs
+            .chunks(fan_in)
+            .map(|group| {
+                let mut merge = Merge::new(group)?;
+                Run::from_sorted(std::iter::from_fn(|| merge.next().transpose()))
+            })
+            .collect::<io::Result<_>>()?;
+    }
+    Ok(runs)
+}
+
+static RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A sorted run in a temporary file, removed when dropped. It is only opened
+/// while being merged.
+struct Run {
+    path: PathBuf,
+}
+
+impl Run {
+    fn write(values: &mut Vec<i64>) -> io::Result<Self> {
+        values.sort();
+        let run = Run::from_sorted(values.iter().map(|v| Ok(*v)))?;
+        values.clear();
+        Ok(run)
+    }
+
+    fn from_sorted<I: Iterator<Item = io::Result<i64>>>(values: I) -> io::Result<Self> {
+        // The temp dir is shared, so never open a file someone else put
+        // there under our name: it may be a symlink to a file we would truncate.
+        let (path, file) = loop {
+            let id = RUN_ID.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!("day1-run-{}-{}", process::id(), id));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        let run = Run { path };
+        let mut writer = BufWriter::new(file);
+        for v in values {
+            writer.write_all(&v?.to_le_bytes())?;
+        }
+        writer.flush()?;
+        Ok(run)
+    }
+
+    fn open(&self) -> io::Result<RunReader> {
+        Ok(RunReader(BufReader::new(File::open(&self.path)?)))
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+struct RunReader(BufReader<File>);
+
+impl RunReader {
+    fn next(&mut self) -> io::Result<Option<i64>> {
+        let mut bytes = [0u8; 8];
+        match self.0.read_exact(&mut bytes) {
+            Ok(()) => Ok(Some(i64::from_le_bytes(bytes))),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+struct Merge {
+    readers: Vec<RunReader>,
+    heap: BinaryHeap<Reverse<(i64, usize)>>,
+}
+
+impl Merge {
+    fn new(runs: &[Run]) -> io::Result<Self> {
+        let mut readers = runs.iter().map(Run::open).collect::<io::Result<Vec<_>>>()?;
+        let mut heap = BinaryHeap::new();
+        for (i, reader) in readers.iter_mut().enumerate() {
+            if let Some(v) = reader.next()? {
+                heap.push(Reverse((v, i)));
+            }
+        }
+        Ok(Merge { readers, heap })
+    }
+
+    fn next(&mut self) -> io::Result<Option<i64>> {
+        let Some(Reverse((v, i))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        if let Some(next) = self.readers[i].next()? {
+            self.heap.push(Reverse((next, i)));
+        }
+        Ok(Some(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: [i64; 6] = [3, 4, 2, 1, 3, 3];
+    const RIGHT: [i64; 6] = [4, 3, 5, 3, 9, 3];
+
+    #[test]
+    fn distance_test() {
+        assert_eq!(distance(&LEFT, &RIGHT, Pairing::Sorted), 11);
+        assert_eq!(distance(&LEFT, &RIGHT, Pairing::Original), 13);
+        assert_eq!(distance(&LEFT, &RIGHT, Pairing::Optimal), 11);
+    }
+
+    #[test]
+    fn distance_optimal_unequal_test() {
+        assert_eq!(distance(&[1, 10], &[9, 2, 100], Pairing::Optimal), 2);
+        assert_eq!(distance(&[9, 2, 100], &[1, 10], Pairing::Optimal), 2);
+    }
+
+    #[test]
+    fn distance_large_ids_test() {
+        let result = distance(&[i64::MIN, i64::MAX], &[0, 0], Pairing::Original);
+        assert_eq!(result, i64::MIN.unsigned_abs() as u128 + i64::MAX as u128);
+
+        let (left, right) = ([i64::MIN; 3], [i64::MAX; 3]);
+        let expected = 3 * u64::MAX as u128;
+        assert_eq!(distance(&left, &right, Pairing::Original), expected);
+        assert_eq!(distance(&left, &right, Pairing::Sorted), expected);
+        assert_eq!(distance(&left, &right, Pairing::Optimal), expected);
+        assert_eq!(
+            distance(&left, &right[..2], Pairing::Optimal),
+            2 * u64::MAX as u128
+        );
+
+        let mut counts = ColumnCounts::new();
+        for (a, b) in left.iter().zip(right.iter()) {
+            counts.add(*a, *b);
+        }
+        assert_eq!(counts.sorted_distance(), expected);
+        let pairs = left.iter().zip(right.iter()).map(|(a, b)| Ok((*a, *b)));
+        assert_eq!(external_sorted_distance(pairs, 1).unwrap(), expected);
+    }
+
+    #[test]
+    fn similarity_large_ids_test() {
+        let (left, right) = ([i64::MAX; 10], [i64::MAX; 10]);
+        let expected = 100 * i64::MAX as i128;
+        assert_eq!(similarity(&left, &right, value_times_count), expected);
+        let mut counts = ColumnCounts::new();
+        for (a, b) in left.iter().zip(right.iter()) {
+            counts.add(*a, *b);
+        }
+        assert_eq!(counts.similarity(value_times_count), expected);
+        assert_eq!(
+            similarity(&[i64::MIN; 2], &[i64::MIN; 2], value_times_count),
+            4 * i64::MIN as i128
+        );
+    }
+
+    #[test]
+    fn similarity_test() {
+        assert_eq!(similarity(&LEFT, &RIGHT, value_times_count), 31);
+        assert_eq!(similarity(&LEFT, &RIGHT, |_, count| count as i128), 10);
+    }
+
+    #[test]
+    fn overlap_test() {
+        assert_eq!(jaccard(&LEFT, &RIGHT), 2.0 / 6.0);
+        assert_eq!(multiset_overlap(&LEFT, &RIGHT), 4);
+    }
+
+    #[test]
+    fn spearman_test() {
+        assert_eq!(spearman(&[1, 2, 3], &[10, 20, 30]), Some(1.0));
+        assert_eq!(spearman(&[1, 2, 3], &[30, 20, 10]), Some(-1.0));
+        assert_eq!(spearman(&[1, 1], &[1, 2]), None);
+    }
+
+    fn column_counts() -> ColumnCounts {
+        let mut counts = ColumnCounts::new();
+        for (a, b) in LEFT.iter().zip(RIGHT.iter()) {
+            counts.add(*a, *b);
+        }
+        counts
+    }
+
+    #[test]
+    fn column_counts_test() {
+        let counts = column_counts();
+        assert_eq!(counts.distinct(), 8);
+        assert_eq!(counts.sorted_distance(), 11);
+        assert_eq!(counts.similarity(value_times_count), 31);
+    }
+
+    #[test]
+    fn external_sorted_distance_test() {
+        let pairs = LEFT.iter().zip(RIGHT.iter()).map(|(a, b)| Ok((*a, *b)));
+        for run_len in 1..8 {
+            let result = external_sorted_distance(pairs.clone(), run_len).unwrap();
+            assert_eq!(result, 11);
+        }
+    }
+
+    #[test]
+    fn run_skips_existing_files_test() {
+        let next = RUN_ID.load(Ordering::Relaxed);
+        let decoys = (next..next + 8)
+            .map(|id| std::env::temp_dir().join(format!("day1-run-{}-{}", process::id(), id)))
+            .collect::<Vec<_>>();
+        for decoy in &decoys {
+            fs::write(decoy, "keep").unwrap();
+        }
+
+        let run = Run::write(&mut vec![3, 1, 2]).unwrap();
+        let mut reader = run.open().unwrap();
+        let values = std::iter::from_fn(|| reader.next().unwrap()).collect::<Vec<_>>();
+        assert_eq!(values, vec![1, 2, 3]);
+        drop(run);
+        for decoy in &decoys {
+            assert_eq!(fs::read_to_string(decoy).unwrap(), "keep");
+            fs::remove_file(decoy).unwrap();
+        }
+    }
+
+    #[test]
+    fn multi_pass_merge_test() {
+        let left = (0..100).map(|x| (x * 37) % 101).collect::<Vec<i64>>();
+        let right = (0..100).map(|x| (x * 53) % 103).collect::<Vec<i64>>();
+        let expected = distance(&left, &right, Pairing::Sorted);
+        let pairs = left.iter().zip(right.iter()).map(|(a, b)| Ok((*a, *b)));
+        for (run_len, fan_in) in [(1, 2), (3, 2), (7, 3), (10, 64)] {
+            let result = merged_distance(pairs.clone(), run_len, fan_in).unwrap();
+            assert_eq!(result, expected);
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use std::collections::BTreeMap;
+use day1::{external_sorted_distance, value_times_count, ColumnCounts};
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, prelude::*, BufReader};
 
@@ -7,32 +9,21 @@ fn main() -> io::Result<()> {
     part2()
 }
 
+const MAX_DISTINCT: usize = 1 << 22;
+const RUN_LEN: usize = 1 << 20;
+
 fn part1() -> io::Result<()> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
-    let mut arr_l: Vec<i32> = Vec::new();
-    let mut arr_r: Vec<i32> = Vec::new();
-
-    for line in reader.lines() {
-        let l = line?;
-        let parts = l.split("   ").collect::<Vec<_>>();
-        let (a, b) = (
-            parts[0].parse::<i32>().unwrap(),
-            parts[1].parse::<i32>().unwrap(),
-        );
-        arr_l.push(a);
-        arr_r.push(b);
-    }
-    arr_l.sort();
-    arr_r.sort();
-
-    let mut sum = 0;
-    for i in 0..arr_l.len() {
-        let a = arr_l[i];
-        let b = arr_r[i];
-        let diff = if a > b { a - b } else { b - a };
-        sum += diff;
-    }
+
+    let sum = match count_columns(reader, MAX_DISTINCT)? {
+        Some(counts) => counts.sorted_distance(),
+        None => {
+            let file = File::open("input.txt")?;
+            let reader = BufReader::new(file);
+            external_sorted_distance(rows(reader), RUN_LEN)?
+        }
+    };
     println!("{}", sum);
 
     Ok(())
@@ -41,30 +32,125 @@ fn part1() -> io::Result<()> {
 fn part2() -> io::Result<()> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
-    let mut arr_l: Vec<i32> = Vec::new();
-    let mut arr_r: BTreeMap<i32, i32> = BTreeMap::new();
-
-    for line in reader.lines() {
-        let l = line?;
-        let parts = l.split("   ").collect::<Vec<_>>();
-        let (a, b) = (
-            parts[0].parse::<i32>().unwrap(),
-            parts[1].parse::<i32>().unwrap(),
-        );
-        arr_l.push(a);
+    let counts = count_all(reader)?;
+
+    let sum = counts.similarity(value_times_count);
+    println!("{}", sum);
+
+    Ok(())
+}
 
-        match arr_r.get(&b) {
-            Some(count) => arr_r.insert(b, count + 1),
-            None => arr_r.insert(b, 1),
-        };
+fn count_columns<R: BufRead>(reader: R, max_distinct: usize) -> io::Result<Option<ColumnCounts>> {
+    let mut counts = ColumnCounts::new();
+    for row in rows(reader) {
+        let (a, b) = row?;
+        counts.add(a, b);
+        if counts.distinct() > max_distinct {
+            return Ok(None);
+        }
     }
+    Ok(Some(counts))
+}
 
-    let mut sum = 0;
-    for a in arr_l {
-        let count = arr_r.get(&a).unwrap_or(&0);
-        sum += a * count;
+fn count_all<R: BufRead>(reader: R) -> io::Result<ColumnCounts> {
+    let mut counts = ColumnCounts::new();
+    for row in rows(reader) {
+        let (a, b) = row?;
+        counts.add(a, b);
     }
-    println!("{}", sum);
+    Ok(counts)
+}
 
-    Ok(())
+#[derive(Debug, PartialEq, Eq)]
+enum RowError {
+    ColumnCount { line: usize, found: usize },
+    NotANumber { line: usize, value: String },
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RowError::ColumnCount { line, found } => {
+                write!(f, "line {}: expected 2 columns, found {}", line, found)
+            }
+            RowError::NotANumber { line, value } => {
+                write!(f, "line {}: {:?} is not a number", line, value)
+            }
+        }
+    }
+}
+
+impl Error for RowError {}
+
+fn rows<R: BufRead>(reader: R) -> impl Iterator<Item = io::Result<(i64, i64)>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|(i, line)| {
+            parse_row(i + 1, line?.as_str())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        })
+}
+
+fn parse_row(line: usize, text: &str) -> Result<(i64, i64), RowError> {
+    let parts = text.split_whitespace().collect::<Vec<_>>();
+    if parts.len() != 2 {
+        return Err(RowError::ColumnCount {
+            line,
+            found: parts.len(),
+        });
+    }
+    let parse = |value: &str| {
+        value.parse::<i64>().map_err(|_| RowError::NotANumber {
+            line,
+            value: value.to_owned(),
+        })
+    };
+    Ok((parse(parts[0])?, parse(parts[1])?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_any_whitespace_test() {
+        let result = rows("3   4\n4\t3\n2 5 \n\n 1    3\n".as_bytes())
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(result, vec![(3, 4), (4, 3), (2, 5), (1, 3)]);
+    }
+
+    #[test]
+    fn parse_row_column_count_test() {
+        assert_eq!(
+            parse_row(7, "1 2 3"),
+            Err(RowError::ColumnCount { line: 7, found: 3 })
+        );
+        assert_eq!(
+            parse_row(8, "12"),
+            Err(RowError::ColumnCount { line: 8, found: 1 })
+        );
+    }
+
+    #[test]
+    fn rows_reports_line_test() {
+        let err = rows("3   4\n4   x\n".as_bytes())
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "line 2: \"x\" is not a number");
+    }
+
+    #[test]
+    fn count_columns_test() {
+        let text = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3";
+        let counts = count_columns(text.as_bytes(), 8).unwrap().unwrap();
+        assert_eq!(counts.sorted_distance(), 11);
+        assert_eq!(counts.similarity(value_times_count), 31);
+        assert_eq!(count_columns(text.as_bytes(), 7).unwrap(), None);
+        let counts = count_all(text.as_bytes()).unwrap();
+        assert_eq!(counts.similarity(value_times_count), 31);
+    }
 }
@@ -1,13 +1,24 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, prelude::*, BufReader};
 
 fn main() -> io::Result<()> {
-    part1().map(|sum| println!("{}", sum))?;
-    part2().map(|sum| println!("{}", sum))
-}
-
-fn part1() -> io::Result<usize> {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let command = match args.first().map(|x| x.as_str()) {
+        Some("rank") | Some("trail") => Some(args.remove(0)),
+        _ => None,
+    };
+    let colour = args
+        .iter()
+        .position(|x| x == "--colour")
+        .map(|i| args.remove(i))
+        .is_some();
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidInput, e);
+    let at = take_option(&mut args, "--at").map_err(invalid)?;
+    let index = take_option(&mut args, "--index").map_err(invalid)?;
+    let rules = TrailRules::from_args(args.into_iter()).map_err(invalid)?;
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
 
@@ -16,173 +27,512 @@ fn part1() -> io::Result<usize> {
         .map(|x| x.unwrap())
         .collect::<Vec<String>>()
         .join("\n");
-    let sum = get_trailheads_score_sum(text.trim());
+    let map = TrailMap::parse(text.trim(), rules)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    match command.as_deref() {
+        Some("rank") => {
+            print!("{}", rank_report(&map));
+            return Ok(());
+        }
+        Some("trail") => {
+            let trail = select_trail(&map, at.as_deref(), index.as_deref())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            print!("{}", map.render_trail(&trail, colour));
+            return Ok(());
+        }
+        _ => (),
+    }
+    let (score_sum, rating_sum) = map.sums();
 
-    Ok(sum)
+    println!("{}", score_sum);
+    println!("{}", rating_sum);
+    Ok(())
 }
 
-fn part2() -> io::Result<usize> {
-    let file = File::open("input.txt")?;
-    let reader = BufReader::new(file);
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let Some(i) = args.iter().position(|x| x == name) else {
+        return Ok(None);
+    };
+    args.remove(i);
+    if args.get(i).is_none_or(|x| x.starts_with("--")) {
+        return Err(format!("missing value for {}", name));
+    }
+    Ok(Some(args.remove(i)))
+}
 
-    let text = reader
-        .lines()
-        .map(|x| x.unwrap())
-        .collect::<Vec<String>>()
-        .join("\n");
-    let sum = get_distinct_trails_sum(text.trim());
+/// Picks the `index`-th trail (default 0) of the trailhead at `row,col`,
+/// or of the best rated trailhead when no position is given.
+fn select_trail(map: &TrailMap, at: Option<&str>, index: Option<&str>) -> Result<Vec<Pos>, String> {
+    let start = match at {
+        Some(at) => {
+            let (row, col) = at
+                .split_once(',')
+                .and_then(|(r, c)| Some((r.parse().ok()?, c.parse().ok()?)))
+                .ok_or(format!("invalid position {}", at))?;
+            Pos { row, col }
+        }
+        None => map
+            .ranked_by_rating()
+            .first()
+            .map(|(p, _)| *p)
+            .ok_or("no trailheads")?,
+    };
+    let index = match index {
+        Some(x) => x
+            .parse::<usize>()
+            .map_err(|_| format!("invalid index {}", x))?,
+        None => 0,
+    };
+    map.trails(start).nth(index).ok_or(format!(
+        "no trail {} from {},{}",
+        index, start.row, start.col
+    ))
+}
 
-    Ok(sum)
+fn rank_report(map: &TrailMap) -> String {
+    let mut res = String::from("by score\n");
+    for (p, score) in map.ranked_by_score() {
+        res += format!("{},{} {}\n", p.row, p.col, score).as_str();
+    }
+    res += "by rating\n";
+    for (p, rating) in map.ranked_by_rating() {
+        res += format!("{},{} {}\n", p.row, p.col, rating).as_str();
+    }
+    res
 }
 
-fn get_trailheads_score_sum(text: &str) -> usize {
-    let (start_positions, map) = parse_text(text);
-    let mut sum = 0;
-    for start_position in start_positions {
-        let mut acc = HashSet::new();
-        count_score(start_position, &map, &mut acc);
-        sum += acc.len();
+struct TrailMap {
+    map: Map,
+    trailheads: Vec<Pos>,
+    rules: TrailRules,
+    stats: TrailStats,
+}
+
+impl TrailMap {
+    fn parse(text: &str, rules: TrailRules) -> Result<Self, MapError> {
+        TrailMap::new(parse_text(text, &rules)?, rules)
+    }
+
+    fn new(map: Map, rules: TrailRules) -> Result<Self, MapError> {
+        let trailheads = map
+            .iter()
+            .enumerate()
+            .flat_map(|(row, cols)| {
+                cols.iter()
+                    .enumerate()
+                    .filter(|(_, height)| **height == Some(rules.start))
+                    .map(move |(col, _)| Pos { row, col })
+            })
+            .collect::<Vec<_>>();
+        let stats = TrailStats::new(&map, &rules)?;
+        // checked once here so that `sums` can add freely
+        trailheads
+            .iter()
+            .try_fold(0usize, |sum, p| sum.checked_add(stats.rating(*p)))
+            .ok_or(MapError::TooManyTrails)?;
+        Ok(TrailMap {
+            map,
+            trailheads,
+            rules,
+            stats,
+        })
     }
-    sum
+
+    fn sums(&self) -> (usize, usize) {
+        self.trailheads.iter().fold((0, 0), |(score, rating), p| {
+            (score + self.stats.score(*p), rating + self.stats.rating(*p))
+        })
+    }
+
+    /// Trailheads with the highest score first, ties in reading order.
+    fn ranked_by_score(&self) -> Vec<(Pos, usize)> {
+        self.ranked(|p| self.stats.score(p))
+    }
+
+    /// Trailheads with the highest rating first, ties in reading order.
+    fn ranked_by_rating(&self) -> Vec<(Pos, usize)> {
+        self.ranked(|p| self.stats.rating(p))
+    }
+
+    fn ranked(&self, value: impl Fn(Pos) -> usize) -> Vec<(Pos, usize)> {
+        let mut res = self
+            .trailheads
+            .iter()
+            .map(|p| (*p, value(*p)))
+            .collect::<Vec<_>>();
+        res.sort_by_key(|(p, value)| (Reverse(*value), *p));
+        res
+    }
+
+    /// Every trail from `start`, one at a time. Steps that cannot reach the
+    /// end height are pruned using the precomputed ratings, so no branch is
+    /// explored in vain.
+    fn trails(&self, start: Pos) -> Trails<'_> {
+        let mut trails = Trails {
+            map: self,
+            path: Vec::new(),
+            pending: Vec::new(),
+        };
+        if self.trailheads.contains(&start) && self.stats.rating(start) > 0 {
+            trails.path.push(start);
+            trails.pending.push(self.useful_steps(start));
+        }
+        trails
+    }
+
+    /// Reversed so that popping visits the steps in neighbour order.
+    fn useful_steps(&self, p: Pos) -> Vec<Pos> {
+        let mut res = steps(p, &self.map, &self.rules)
+            .filter(|n| self.stats.rating(*n) > 0)
+            .collect::<Vec<_>>();
+        res.reverse();
+        res
+    }
+
+    /// Without colour only the trail's heights are shown, the way the puzzle
+    /// draws its examples; with colour the whole map is kept and the trail
+    /// highlighted. Heights too large for a single letter show as `+`.
+    fn render_trail(&self, trail: &[Pos], colour: bool) -> String {
+        let mut res = String::new();
+        for (row, cols) in self.map.iter().enumerate() {
+            for (col, height) in cols.iter().enumerate() {
+                let ch = match height {
+                    Some(h) => char::from_digit(*h as u32, 36).unwrap_or('+'),
+                    None => '.',
+                };
+                let on_trail = trail.contains(&Pos { row, col });
+                match (on_trail, colour) {
+                    (true, true) => res += format!("\x1b[32m{}\x1b[0m", ch).as_str(),
+                    (true, false) | (false, true) => res.push(ch),
+                    (false, false) => res.push('.'),
+                }
+            }
+            res.push('\n');
+        }
+        res
+    }
+}
+
+/// Depth-first walk kept on an explicit stack: `pending[i]` holds the steps
+/// still to try from `path[i]`, so the call depth stays constant however
+/// long the trails get.
+struct Trails<'a> {
+    map: &'a TrailMap,
+    path: Vec<Pos>,
+    pending: Vec<Vec<Pos>>,
 }
 
-fn get_distinct_trails_sum(text: &str) -> usize {
-    let (start_positions, map) = parse_text(text);
-    let mut sum = 0;
-    for start_position in start_positions {
-        sum += count_trails(start_position, &map);
+impl Iterator for Trails<'_> {
+    type Item = Vec<Pos>;
+
+    fn next(&mut self) -> Option<Vec<Pos>> {
+        while let Some(choices) = self.pending.last_mut() {
+            let Some(next) = choices.pop() else {
+                self.pending.pop();
+                self.path.pop();
+                continue;
+            };
+            self.path.push(next);
+            if self.map.map[next.row][next.col] == Some(self.map.rules.end) {
+                let trail = self.path.clone();
+                self.path.pop();
+                return Some(trail);
+            }
+            self.pending.push(self.map.useful_steps(next));
+        }
+        None
     }
-    sum
 }
 
-fn count_score(start: Pos, map: &Vec<Vec<u8>>, acc: &mut HashSet<Pos>) {
-    let current = map[start.row][start.col];
-    if current == 9 {
-        acc.insert(start);
-    } else {
-        if start.row > 0 && map[start.row - 1][start.col] == current + 1 {
-            count_score(
-                Pos {
-                    row: start.row - 1,
-                    col: start.col,
-                },
-                map,
-                acc,
-            );
-        };
-        if start.row + 1 < map.len() && map[start.row + 1][start.col] == current + 1 {
-            count_score(
-                Pos {
-                    row: start.row + 1,
-                    col: start.col,
-                },
-                map,
-                acc,
-            );
-        };
-        if start.col > 0 && map[start.row][start.col - 1] == current + 1 {
-            count_score(
-                Pos {
-                    row: start.row,
-                    col: start.col - 1,
-                },
-                map,
-                acc,
-            );
-        };
-        if start.col + 1 < map[start.row].len() && map[start.row][start.col + 1] == current + 1 {
-            count_score(
-                Pos {
-                    row: start.row,
-                    col: start.col + 1,
-                },
-                map,
-                acc,
-            );
-        };
+/// `None` marks an impassable tile.
+type Map = Vec<Vec<Option<u16>>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrailRules {
+    start: u16,
+    end: u16,
+    min_delta: i16,
+    max_delta: i16,
+    diagonal: bool,
+    impassable: Vec<char>,
+    /// Rows are whitespace-separated numbers rather than one tile per
+    /// character.
+    numeric: bool,
+}
+
+impl Default for TrailRules {
+    fn default() -> Self {
+        TrailRules {
+            start: 0,
+            end: 9,
+            min_delta: 1,
+            max_delta: 1,
+            diagonal: false,
+            impassable: vec!['.'],
+            numeric: false,
+        }
     }
 }
 
-fn count_trails(start: Pos, map: &Vec<Vec<u8>>) -> usize {
-    let current = map[start.row][start.col];
-    if current == 9 {
-        1
-    } else {
-        let up = if start.row > 0 && map[start.row - 1][start.col] == current + 1 {
-            count_trails(
-                Pos {
-                    row: start.row - 1,
-                    col: start.col,
-                },
-                map,
-            )
-        } else {
-            0
-        };
-        let down = if start.row + 1 < map.len() && map[start.row + 1][start.col] == current + 1 {
-            count_trails(
-                Pos {
-                    row: start.row + 1,
-                    col: start.col,
-                },
-                map,
-            )
-        } else {
-            0
-        };
-        let left = if start.col > 0 && map[start.row][start.col - 1] == current + 1 {
-            count_trails(
-                Pos {
-                    row: start.row,
-                    col: start.col - 1,
-                },
-                map,
-            )
+impl TrailRules {
+    fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut rules = TrailRules::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+            match arg.as_str() {
+                "--start" => rules.start = parse_height(&value()?)?,
+                "--end" => rules.end = parse_height(&value()?)?,
+                "--min-delta" => rules.min_delta = parse_delta(&value()?)?,
+                "--max-delta" => rules.max_delta = parse_delta(&value()?)?,
+                "--diagonal" => rules.diagonal = true,
+                "--impassable" => rules.impassable = value()?.chars().collect(),
+                "--numeric" => rules.numeric = true,
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
+        }
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    /// Every step has to move strictly towards the end height, otherwise a
+    /// trail could loop forever and the ratings would be unbounded.
+    fn validate(&self) -> Result<(), String> {
+        if self.min_delta > self.max_delta {
+            return Err(format!(
+                "min delta {} is greater than max delta {}",
+                self.min_delta, self.max_delta
+            ));
+        }
+        let towards_end = if self.ascending() {
+            self.min_delta > 0
         } else {
-            0
+            self.max_delta < 0
         };
-        let right = if start.col + 1 < map[start.row].len()
-            && map[start.row][start.col + 1] == current + 1
-        {
-            count_trails(
-                Pos {
-                    row: start.row,
-                    col: start.col + 1,
-                },
-                map,
-            )
+        if self.start == self.end || !towards_end {
+            return Err(format!(
+                "delta range {}..={} does not lead from {} to {}",
+                self.min_delta, self.max_delta, self.start, self.end
+            ));
+        }
+        Ok(())
+    }
+
+    fn ascending(&self) -> bool {
+        self.end > self.start
+    }
+
+    fn allows_step(&self, from: u16, to: u16) -> bool {
+        let delta = to as i32 - from as i32;
+        (self.min_delta as i32..=self.max_delta as i32).contains(&delta)
+    }
+}
+
+fn parse_height(value: &str) -> Result<u16, String> {
+    value
+        .parse::<u16>()
+        .map_err(|_| format!("invalid height {}", value))
+}
+
+fn parse_delta(value: &str) -> Result<i16, String> {
+    value
+        .parse::<i16>()
+        .map_err(|_| format!("invalid delta {}", value))
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Bitset(Vec<u64>);
+
+impl Bitset {
+    fn new(size: usize) -> Self {
+        Bitset(vec![0; size.div_ceil(64)])
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn union_with(&mut self, other: &Bitset) {
+        for (a, b) in self.0.iter_mut().zip(&other.0) {
+            *a |= b;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|x| x.count_ones() as usize).sum()
+    }
+}
+
+/// Scores and ratings of every cell, filled in one pass over the cells
+/// ordered by height rather than by recursing along the trails.
+struct TrailStats {
+    summits: Vec<Vec<Bitset>>,
+    trails: Vec<Vec<usize>>,
+}
+
+impl TrailStats {
+    fn new(map: &Map, rules: &TrailRules) -> Result<Self, MapError> {
+        let mut cells = map
+            .iter()
+            .enumerate()
+            .flat_map(|(row, cols)| {
+                cols.iter()
+                    .enumerate()
+                    .filter_map(move |(col, height)| height.map(|h| (h, Pos { row, col })))
+            })
+            .collect::<Vec<_>>();
+        let summit_cells = cells
+            .iter()
+            .filter(|(h, _)| *h == rules.end)
+            .map(|(_, p)| *p)
+            .collect::<Vec<_>>();
+        let empty = Bitset::new(summit_cells.len());
+        let mut summits = map
+            .iter()
+            .map(|cols| vec![empty.clone(); cols.len()])
+            .collect::<Vec<_>>();
+        let mut trails = map
+            .iter()
+            .map(|cols| vec![0usize; cols.len()])
+            .collect::<Vec<_>>();
+        for (i, p) in summit_cells.iter().enumerate() {
+            summits[p.row][p.col].insert(i);
+            trails[p.row][p.col] = 1;
+        }
+
+        // every step moves strictly towards the end height, so visiting the
+        // cells closest to it first only ever reads cells that are final
+        if rules.ascending() {
+            cells.sort_by_key(|(h, _)| Reverse(*h));
         } else {
-            0
-        };
-        up + down + left + right
+            cells.sort_by_key(|(h, _)| *h);
+        }
+        for (height, p) in cells {
+            if height == rules.end {
+                continue;
+            }
+            // taken out while its neighbours are merged in, which never
+            // include `p` itself, so nothing is copied per step
+            let mut reachable = std::mem::take(&mut summits[p.row][p.col]);
+            for n in steps(p, map, rules) {
+                reachable.union_with(&summits[n.row][n.col]);
+                trails[p.row][p.col] = trails[p.row][p.col]
+                    .checked_add(trails[n.row][n.col])
+                    .ok_or(MapError::TooManyTrails)?;
+            }
+            summits[p.row][p.col] = reachable;
+        }
+        Ok(TrailStats { summits, trails })
+    }
+
+    fn score(&self, p: Pos) -> usize {
+        self.summits[p.row][p.col].len()
+    }
+
+    fn rating(&self, p: Pos) -> usize {
+        self.trails[p.row][p.col]
+    }
+}
+
+/// Neighbours of a passable `p` that a trail may step onto next.
+fn steps<'a>(p: Pos, map: &'a Map, rules: &'a TrailRules) -> impl Iterator<Item = Pos> + 'a {
+    let height = map[p.row][p.col];
+    neighbours(p, map, rules.diagonal).into_iter().filter(move |n| {
+        matches!((height, map[n.row][n.col]), (Some(h), Some(next)) if rules.allows_step(h, next))
+    })
+}
+
+fn neighbours(p: Pos, map: &Map, diagonal: bool) -> Vec<Pos> {
+    let mut res = Vec::with_capacity(8);
+    for d_row in -1..=1isize {
+        for d_col in -1..=1isize {
+            if (d_row, d_col) == (0, 0) || (!diagonal && d_row != 0 && d_col != 0) {
+                continue;
+            }
+            let (Some(row), Some(col)) = (
+                p.row.checked_add_signed(d_row),
+                p.col.checked_add_signed(d_col),
+            ) else {
+                continue;
+            };
+            if row < map.len() && col < map[row].len() {
+                res.push(Pos { row, col });
+            }
+        }
+    }
+    res
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum MapError {
+    UnexpectedTile {
+        line: usize,
+        column: usize,
+        tile: String,
+    },
+    TooManyTrails,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapError::UnexpectedTile { line, column, tile } => {
+                write!(
+                    f,
+                    "line {}, column {}: unexpected tile {:?}",
+                    line, column, tile
+                )
+            }
+            MapError::TooManyTrails => write!(f, "too many distinct trails to count"),
+        }
     }
 }
 
-fn parse_text(text: &str) -> (Vec<Pos>, Vec<Vec<u8>>) {
-    let mut start_positions = Vec::new();
+impl Error for MapError {}
+
+/// One tile per character, heights above 9 written as letters with `a`
+/// being 10, or with `rules.numeric` whitespace-separated numbers for
+/// heights up to 65535.
+fn parse_text(text: &str, rules: &TrailRules) -> Result<Map, MapError> {
+    let numeric = rules.numeric;
     let mut map = Vec::new();
-    for line in text.lines() {
+    for (row, line) in text.lines().enumerate() {
+        let tiles = if numeric {
+            line.split_whitespace().collect::<Vec<_>>()
+        } else {
+            line.char_indices()
+                .map(|(i, ch)| &line[i..i + ch.len_utf8()])
+                .collect()
+        };
         let mut cols = Vec::new();
-        for char in line.chars() {
-            if char != '.' {
-                cols.push(char.to_string().parse::<u8>().unwrap());
-                if char == '0' {
-                    start_positions.push(Pos {
-                        row: map.len(),
-                        col: cols.len() - 1,
-                    });
+        for (col, tile) in tiles.into_iter().enumerate() {
+            let mut chars = tile.chars();
+            if let (Some(ch), None) = (chars.next(), chars.next()) {
+                if rules.impassable.contains(&ch) {
+                    cols.push(None);
+                    continue;
                 }
-            } else {
-                cols.push(0);
             }
+            let height = if numeric {
+                tile.parse::<u16>().ok()
+            } else {
+                tile.chars()
+                    .next()
+                    .and_then(|ch| ch.to_digit(36))
+                    .map(|h| h as u16)
+            };
+            let height = height.ok_or(MapError::UnexpectedTile {
+                line: row + 1,
+                column: col + 1,
+                tile: tile.to_string(),
+            })?;
+            cols.push(Some(height));
         }
         map.push(cols);
     }
-    // println!("{:?}\n{:?}", start_positions, map);
-    (start_positions, map)
+    Ok(map)
 }
 
-#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone)]
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Debug, Copy, Clone)]
 struct Pos {
     row: usize,
     col: usize,
@@ -192,9 +542,13 @@ struct Pos {
 mod tests {
     use super::*;
 
+    fn get_trailheads_sums(text: &str, rules: &TrailRules) -> (usize, usize) {
+        TrailMap::parse(text, rules.clone()).unwrap().sums()
+    }
+
     #[test]
     fn get_trailheads_score_sum2_test() {
-        let result = get_trailheads_score_sum(
+        let result = get_trailheads_sums(
             "...0...
 ...1...
 ...2...
@@ -202,13 +556,15 @@ mod tests {
 7.....7
 8.....8
 9.....9",
-        );
+            &TrailRules::default(),
+        )
+        .0;
         assert_eq!(result, 2);
     }
 
     #[test]
     fn get_trailheads_score_sum3_test() {
-        let result = get_trailheads_score_sum(
+        let result = get_trailheads_sums(
             "10..9..
 2...8..
 3...7..
@@ -216,13 +572,15 @@ mod tests {
 ...8..3
 ...9..2
 .....01",
-        );
+            &TrailRules::default(),
+        )
+        .0;
         assert_eq!(result, 3);
     }
 
     #[test]
     fn get_trailheads_score_sum4_test() {
-        let result = get_trailheads_score_sum(
+        let result = get_trailheads_sums(
             "..90..9
 ...1.98
 ...2..7
@@ -230,13 +588,15 @@ mod tests {
 765.987
 876....
 987....",
-        );
+            &TrailRules::default(),
+        )
+        .0;
         assert_eq!(result, 4);
     }
 
     #[test]
     fn get_trailheads_score_sum36_test() {
-        let result = get_trailheads_score_sum(
+        let result = get_trailheads_sums(
             "89010123
 78121874
 87430965
@@ -245,13 +605,15 @@ mod tests {
 32019012
 01329801
 10456732",
-        );
+            &TrailRules::default(),
+        )
+        .0;
         assert_eq!(result, 36);
     }
 
     #[test]
     fn get_distinct_trails_sum3_test() {
-        let result = get_distinct_trails_sum(
+        let result = get_trailheads_sums(
             ".....0.
 ..4321.
 ..5..2.
@@ -259,13 +621,15 @@ mod tests {
 ..7..4.
 ..8765.
 ..9....",
-        );
+            &TrailRules::default(),
+        )
+        .1;
         assert_eq!(result, 3);
     }
 
     #[test]
     fn get_distinct_trails_sum13_test() {
-        let result = get_distinct_trails_sum(
+        let result = get_trailheads_sums(
             "..90..9
 ...1.98
 ...2..7
@@ -273,26 +637,30 @@ mod tests {
 765.987
 876....
 987....",
-        );
+            &TrailRules::default(),
+        )
+        .1;
         assert_eq!(result, 13);
     }
 
     #[test]
     fn get_distinct_trails_sum227_test() {
-        let result = get_distinct_trails_sum(
+        let result = get_trailheads_sums(
             "012345
 123456
 234567
 345678
 4.6789
 56789.",
-        );
+            &TrailRules::default(),
+        )
+        .1;
         assert_eq!(result, 227);
     }
 
     #[test]
     fn get_distinct_trails_sum81_test() {
-        let result = get_distinct_trails_sum(
+        let result = get_trailheads_sums(
             "89010123
 78121874
 87430965
@@ -301,7 +669,350 @@ mod tests {
 32019012
 01329801
 10456732",
-        );
+            &TrailRules::default(),
+        )
+        .1;
         assert_eq!(result, 81);
     }
+
+    #[test]
+    fn trail_stats_test() {
+        let rules = TrailRules::default();
+        let map = parse_text(
+            "89010123
+78121874
+87430965
+96549874
+45678903
+32019012
+01329801
+10456732",
+            &rules,
+        )
+        .unwrap();
+        let stats = TrailStats::new(&map, &rules).unwrap();
+        assert_eq!(stats.score(Pos { row: 0, col: 2 }), 5);
+        assert_eq!(stats.rating(Pos { row: 0, col: 2 }), 20);
+        assert_eq!(stats.score(Pos { row: 6, col: 6 }), 3);
+        assert_eq!(stats.rating(Pos { row: 6, col: 6 }), 8);
+    }
+
+    #[test]
+    fn trail_rules_diagonal_test() {
+        let mut rules = TrailRules {
+            end: 2,
+            ..TrailRules::default()
+        };
+        let text = "0..
+.1.
+..2";
+        assert_eq!(get_trailheads_sums(text, &rules), (0, 0));
+        rules.diagonal = true;
+        assert_eq!(get_trailheads_sums(text, &rules), (1, 1));
+    }
+
+    #[test]
+    fn trail_rules_delta_range_test() {
+        let mut rules = TrailRules {
+            end: 3,
+            ..TrailRules::default()
+        };
+        let text = "01
+23";
+        assert_eq!(get_trailheads_sums(text, &rules), (0, 0));
+        rules.max_delta = 2;
+        assert_eq!(get_trailheads_sums(text, &rules), (1, 2));
+    }
+
+    #[test]
+    fn trail_rules_descending_test() {
+        let rules = TrailRules {
+            start: 9,
+            end: 0,
+            min_delta: -1,
+            max_delta: -1,
+            ..TrailRules::default()
+        };
+        let (score, rating) = get_trailheads_sums(
+            "89010123
+78121874
+87430965
+96549874
+45678903
+32019012
+01329801
+10456732",
+            &rules,
+        );
+        // walking down reaches the same summit/trailhead pairs and trails
+        assert_eq!((score, rating), (36, 81));
+    }
+
+    #[test]
+    fn trail_rules_impassable_test() {
+        let rules = TrailRules {
+            end: 11,
+            impassable: vec!['#'],
+            ..TrailRules::default()
+        };
+        assert_eq!(get_trailheads_sums("0123456789ab", &rules), (1, 1));
+        assert_eq!(get_trailheads_sums("01234#6789ab", &rules), (0, 0));
+    }
+
+    #[test]
+    fn numeric_map_test() {
+        let rules = TrailRules {
+            end: 300,
+            max_delta: 100,
+            numeric: true,
+            ..TrailRules::default()
+        };
+        let map = TrailMap::parse("0 100 .\n. 200 300", rules.clone()).unwrap();
+        assert_eq!(map.map[1], vec![None, Some(200), Some(300)]);
+        assert_eq!(map.sums(), (1, 1));
+        let trail = map.trails(Pos { row: 0, col: 0 }).next().unwrap();
+        assert_eq!(map.render_trail(&trail, false), "0+.\n.++\n");
+
+        assert_eq!(
+            parse_text("0 1\n2 70000", &rules),
+            Err(MapError::UnexpectedTile {
+                line: 2,
+                column: 2,
+                tile: "70000".to_string(),
+            })
+        );
+
+        let map = TrailMap::parse("0\n100\n200", rules.clone()).unwrap();
+        assert_eq!(
+            map.map,
+            vec![vec![Some(0)], vec![Some(100)], vec![Some(200)]]
+        );
+        assert_eq!(map.sums(), (0, 0));
+        assert_eq!(
+            parse_text("0\n100\n200", &TrailRules::default())
+                .unwrap()
+                .concat(),
+            vec![
+                Some(0),
+                Some(1),
+                Some(0),
+                Some(0),
+                Some(2),
+                Some(0),
+                Some(0)
+            ]
+        );
+        assert_eq!(
+            parse_text("0 1", &TrailRules::default()),
+            Err(MapError::UnexpectedTile {
+                line: 1,
+                column: 2,
+                tile: " ".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn take_option_test() {
+        let mut args = ["--at", "1,2", "--diagonal"].map(String::from).to_vec();
+        assert_eq!(take_option(&mut args, "--at"), Ok(Some("1,2".to_string())));
+        assert_eq!(take_option(&mut args, "--index"), Ok(None));
+        assert_eq!(args, vec!["--diagonal"]);
+        let mut args = ["--diagonal", "--index"].map(String::from).to_vec();
+        assert!(take_option(&mut args, "--index").is_err());
+        let mut args = ["--at", "--diagonal"].map(String::from).to_vec();
+        assert!(take_option(&mut args, "--at").is_err());
+    }
+
+    #[test]
+    fn parse_text_error_test() {
+        let err = parse_text("012\n3#4", &TrailRules::default()).unwrap_err();
+        assert_eq!(
+            err,
+            MapError::UnexpectedTile {
+                line: 2,
+                column: 2,
+                tile: "#".to_string(),
+            }
+        );
+        assert_eq!(err.to_string(), "line 2, column 2: unexpected tile \"#\"");
+        assert!(TrailMap::parse("0#", TrailRules::default()).is_err());
+    }
+
+    #[test]
+    fn trail_rules_from_args_test() {
+        let args = [
+            "--end",
+            "5",
+            "--max-delta",
+            "2",
+            "--diagonal",
+            "--impassable",
+            "#.",
+            "--numeric",
+        ];
+        let rules = TrailRules::from_args(args.iter().map(|x| x.to_string())).unwrap();
+        assert_eq!(
+            rules,
+            TrailRules {
+                start: 0,
+                end: 5,
+                min_delta: 1,
+                max_delta: 2,
+                diagonal: true,
+                impassable: vec!['#', '.'],
+                numeric: true,
+            }
+        );
+        let args = ["--min-delta", "0"];
+        assert!(TrailRules::from_args(args.iter().map(|x| x.to_string())).is_err());
+        let args = ["--start", "9", "--end", "0"];
+        assert!(TrailRules::from_args(args.iter().map(|x| x.to_string())).is_err());
+        let args = ["--end"];
+        assert!(TrailRules::from_args(args.iter().map(|x| x.to_string())).is_err());
+    }
+
+    const EXAMPLE: &str = "89010123
+78121874
+87430965
+96549874
+45678903
+32019012
+01329801
+10456732";
+
+    #[test]
+    fn trails_match_ratings_test() {
+        let map = TrailMap::parse(EXAMPLE, TrailRules::default()).unwrap();
+        for p in &map.trailheads {
+            let trails = map.trails(*p).collect::<Vec<_>>();
+            assert_eq!(trails.len(), map.stats.rating(*p));
+            for trail in trails {
+                assert_eq!(trail.len(), 10);
+                assert_eq!(trail[0], *p);
+                assert_eq!(map.map[trail[9].row][trail[9].col], Some(9));
+            }
+        }
+        assert_eq!(map.trails(Pos { row: 0, col: 0 }).next(), None);
+    }
+
+    #[test]
+    fn render_trail_test() {
+        let map = TrailMap::parse(
+            ".....0.
+..4321.
+..5..2.
+..6543.
+..7..4.
+..8765.
+..9....",
+            TrailRules::default(),
+        )
+        .unwrap();
+        let trail = map.trails(Pos { row: 0, col: 5 }).next().unwrap();
+        assert_eq!(
+            map.render_trail(&trail, false),
+            ".....0.
+..4321.
+..5....
+..6....
+..7....
+..8....
+..9....
+"
+        );
+        let coloured = map.render_trail(&trail, true);
+        assert!(coloured.starts_with(".....\x1b[32m0\x1b[0m.\n"));
+        assert!(coloured.contains("..\x1b[32m5\x1b[0m..2.\n"));
+    }
+
+    #[test]
+    fn ranked_trailheads_test() {
+        let map = TrailMap::parse(EXAMPLE, TrailRules::default()).unwrap();
+        let pos = |row, col| Pos { row, col };
+        assert_eq!(
+            map.ranked_by_score(),
+            vec![
+                (pos(0, 4), 6),
+                (pos(0, 2), 5),
+                (pos(2, 4), 5),
+                (pos(6, 0), 5),
+                (pos(7, 1), 5),
+                (pos(4, 6), 3),
+                (pos(5, 5), 3),
+                (pos(6, 6), 3),
+                (pos(5, 2), 1),
+            ]
+        );
+        assert_eq!(
+            map.ranked_by_rating(),
+            vec![
+                (pos(0, 4), 24),
+                (pos(0, 2), 20),
+                (pos(2, 4), 10),
+                (pos(6, 6), 8),
+                (pos(6, 0), 5),
+                (pos(7, 1), 5),
+                (pos(4, 6), 4),
+                (pos(5, 5), 4),
+                (pos(5, 2), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_slope_test() {
+        // a boustrophedon slope climbing by one through every cell; walking
+        // it recursively would need one frame per step
+        let size = 256;
+        let map = (0..size)
+            .map(|row| {
+                (0..size)
+                    .map(|col| {
+                        let col = if row % 2 == 0 { col } else { size - 1 - col };
+                        Some((row * size + col) as u16)
+                    })
+                    .collect()
+            })
+            .collect();
+        let rules = TrailRules {
+            end: (size * size - 1) as u16,
+            ..TrailRules::default()
+        };
+        let map = TrailMap::new(map, rules).unwrap();
+        assert_eq!(map.sums(), (1, 1));
+        let trails = map.trails(Pos { row: 0, col: 0 }).collect::<Vec<_>>();
+        assert_eq!(trails.len(), 1);
+        assert_eq!(trails[0].len(), size * size);
+        assert_eq!(
+            trails[0].last(),
+            Some(&Pos {
+                row: size - 1,
+                col: 0
+            })
+        );
+    }
+
+    #[test]
+    fn too_many_trails_test() {
+        // every row doubles the trails, as each cell steps diagonally or
+        // straight onto both cells of the next row
+        let strip = |rows: usize| {
+            let map = (0..rows).map(|row| vec![Some(row as u16); 2]).collect();
+            let rules = TrailRules {
+                end: rows as u16 - 1,
+                diagonal: true,
+                ..TrailRules::default()
+            };
+            TrailMap::new(map, rules)
+        };
+        let bits = usize::BITS as usize;
+        // two trailheads of 2^(bits - 2) trails each still add up
+        let map = strip(bits - 1).unwrap();
+        assert_eq!(map.sums().1, 1 << (bits - 1));
+        // the two trailheads together overflow
+        assert_eq!(strip(bits).err(), Some(MapError::TooManyTrails));
+        // a single trailhead overflows
+        assert_eq!(strip(bits + 1).err(), Some(MapError::TooManyTrails));
+    }
 }
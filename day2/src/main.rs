@@ -2,11 +2,25 @@ use std::fs::File;
 use std::io::{self, prelude::*, BufReader};
 
 fn main() -> io::Result<()> {
-    part1().map(|safe_count| println!("{}", safe_count))?;
-    part2().map(|safe_count| println!("{}", safe_count))
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let diagnose = args.first().map(|x| x.as_str()) == Some("diagnose");
+    if diagnose {
+        args.remove(0);
+    }
+    let csv = args
+        .iter()
+        .position(|x| x == "--csv")
+        .map(|i| args.remove(i));
+    let policy = SafetyPolicy::from_args(args.into_iter())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if diagnose {
+        return diagnose_reports(&policy, csv.is_some()).map(|listing| print!("{}", listing));
+    }
+    part1(&policy).map(|safe_count| println!("{}", safe_count))?;
+    part2(&policy).map(|safe_count| println!("{}", safe_count))
 }
 
-fn part1() -> io::Result<i32> {
+fn part1(policy: &SafetyPolicy) -> io::Result<i32> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
     let mut safe_count = 0;
@@ -17,14 +31,14 @@ fn part1() -> io::Result<i32> {
             .map(|x| x.parse::<i32>().unwrap())
             .collect();
 
-        if is_safe(levels) {
+        if is_safe(levels, policy) {
             safe_count += 1;
         }
     }
     Ok(safe_count)
 }
 
-fn part2() -> io::Result<i32> {
+fn part2(policy: &SafetyPolicy) -> io::Result<i32> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
     let mut safe_count = 0;
@@ -35,122 +49,527 @@ fn part2() -> io::Result<i32> {
             .map(|x| x.parse::<i32>().unwrap())
             .collect();
 
-        if is_safe_with_dampener(levels, 1) {
+        if is_safe_with_dampener(levels, 1, policy) {
             safe_count += 1;
         }
     }
     Ok(safe_count)
 }
 
-fn is_safe(levels: Vec<i32>) -> bool {
-    is_safe_with_dampener(levels, 0)
-}
-
-fn is_safe_with_dampener(levels: Vec<i32>, max_fails: i32) -> bool {
-    if is_safe_with_dampener_impl(levels.clone(), max_fails) {
-        true
-    } else if max_fails > 0 {
-        let mut levels_l = levels.clone();
-        levels_l.remove(0);
-        if is_safe_with_dampener_impl(levels_l, max_fails - 1) {
-            true
-        } else if max_fails > 0 {
-            let mut levels_r = levels.clone();
-            levels_r.remove(levels_r.len() - 1);
-            if is_safe_with_dampener_impl(levels_r, max_fails - 1) {
-                true
-            } else {
-                false
+fn diagnose_reports(policy: &SafetyPolicy, csv: bool) -> io::Result<String> {
+    let file = File::open("input.txt")?;
+    let reader = BufReader::new(file);
+    let mut listing = String::new();
+    if csv {
+        listing += "report,levels,status,pair_index,delta,rule,removed\n";
+    }
+
+    for (i, line) in reader.lines().enumerate() {
+        let levels = line?
+            .split_ascii_whitespace()
+            .map(|x| x.parse::<i32>().unwrap())
+            .collect::<Vec<_>>();
+        let diagnosis = diagnose(&levels, 1, policy);
+        let row = if csv {
+            diagnosis.to_csv(i + 1, &levels)
+        } else {
+            diagnosis.to_text(i + 1, &levels)
+        };
+        listing += row.as_str();
+        listing += "\n";
+    }
+    Ok(listing)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Rule {
+    WrongDirection,
+    Equal,
+    StepTooSmall,
+    StepTooLarge,
+}
+
+impl Rule {
+    fn name(&self) -> &'static str {
+        match self {
+            Rule::WrongDirection => "wrong direction",
+            Rule::Equal => "equal neighbours",
+            Rule::StepTooSmall => "step too small",
+            Rule::StepTooLarge => "step too large",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Violation {
+    index: usize,
+    delta: i32,
+    rule: Rule,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diagnosis {
+    violation: Option<Violation>,
+    removed: Option<Vec<usize>>,
+}
+
+impl Diagnosis {
+    fn status(&self) -> &'static str {
+        match (&self.violation, &self.removed) {
+            (None, _) => "safe",
+            (Some(_), Some(_)) => "dampened",
+            (Some(_), None) => "unsafe",
+        }
+    }
+
+    fn to_text(&self, report: usize, levels: &[i32]) -> String {
+        let levels_text = levels
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut text = format!("{}: {} {}", report, levels_text, self.status());
+        if let Some(v) = self.violation {
+            text += format!(
+                ", levels {} and {} ({} -> {}) delta {:+} {}",
+                v.index,
+                v.index + 1,
+                levels[v.index],
+                levels[v.index + 1],
+                v.delta,
+                v.rule.name()
+            )
+            .as_str();
+        }
+        for i in self.removed.iter().flatten() {
+            text += format!(", removed level {} at {}", levels[*i], i).as_str();
+        }
+        text
+    }
+
+    fn to_csv(&self, report: usize, levels: &[i32]) -> String {
+        let levels_text = levels
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (index, delta, rule) = match self.violation {
+            Some(v) => (v.index.to_string(), v.delta.to_string(), v.rule.name()),
+            None => (String::new(), String::new(), ""),
+        };
+        let removed = self
+            .removed
+            .iter()
+            .flatten()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "{},{},{},{},{},{},{}",
+            report,
+            levels_text,
+            self.status(),
+            index,
+            delta,
+            rule,
+            removed
+        )
+    }
+}
+
+fn diagnose(levels: &[i32], max_removals: usize, policy: &SafetyPolicy) -> Diagnosis {
+    // judge against the allowed direction that holds for the longest prefix,
+    // falling back to the one most neighbour pairs agree with
+    let violation = policy
+        .directions()
+        .into_iter()
+        .max_by_key(|&increasing| {
+            let first = first_violation(levels, increasing, policy);
+            let agreeing = levels
+                .windows(2)
+                .filter(|pair| (pair[0] < pair[1]) == increasing && pair[0] != pair[1])
+                .count();
+            (first.map_or(usize::MAX, |v| v.index), agreeing)
+        })
+        .map(|increasing| first_violation(levels, increasing, policy));
+    let violation = violation.flatten();
+    let removed = match violation {
+        Some(_) => dampened_removals(levels, max_removals, policy),
+        None => None,
+    };
+    Diagnosis { violation, removed }
+}
+
+fn first_violation(levels: &[i32], increasing: bool, policy: &SafetyPolicy) -> Option<Violation> {
+    levels.windows(2).enumerate().find_map(|(index, pair)| {
+        let delta = pair[1] - pair[0];
+        let step = if increasing { delta } else { -delta };
+        let rule = if step == 0 {
+            if policy.allow_equal {
+                return None;
             }
+            Rule::Equal
+        } else if step < 0 {
+            Rule::WrongDirection
+        } else if step < policy.min_step {
+            Rule::StepTooSmall
+        } else if step > policy.max_step {
+            Rule::StepTooLarge
         } else {
-            false
+            return None;
+        };
+        Some(Violation { index, delta, rule })
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SafetyPolicy {
+    min_step: i32,
+    max_step: i32,
+    increasing: bool,
+    decreasing: bool,
+    allow_equal: bool,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        SafetyPolicy {
+            min_step: 1,
+            max_step: 3,
+            increasing: true,
+            decreasing: true,
+            allow_equal: false,
         }
-    } else {
-        false
     }
 }
 
-fn is_safe_with_dampener_impl(levels: Vec<i32>, max_fails: i32) -> bool {
-    let increasing = levels[0] < levels[1];
-    let mut prev = levels[0];
-    for i in 1..levels.len() {
-        let curr = levels[i];
-        let diff = if increasing { curr - prev } else { prev - curr };
-        if diff < 1 || diff > 3 {
-            return if max_fails > 0 {
-                let mut levels_l = levels.clone();
-                levels_l.remove(i - 1);
-                if is_safe_with_dampener_impl(levels_l, max_fails - 1) {
-                    true
-                } else {
-                    let mut levels_r = levels.clone();
-                    levels_r.remove(i);
-                    is_safe_with_dampener_impl(levels_r, max_fails - 1)
+impl SafetyPolicy {
+    fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut policy = SafetyPolicy::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+            match arg.as_str() {
+                "--min-step" => policy.min_step = parse_step(&value()?)?,
+                "--max-step" => policy.max_step = parse_step(&value()?)?,
+                "--direction" => {
+                    let direction = value()?;
+                    (policy.increasing, policy.decreasing) = match direction.as_str() {
+                        "increasing" => (true, false),
+                        "decreasing" => (false, true),
+                        "any" => (true, true),
+                        _ => return Err(format!("unknown direction {}", direction)),
+                    }
                 }
-            } else {
-                false
-            };
+                "--allow-equal" => policy.allow_equal = true,
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
+        }
+        if policy.min_step > policy.max_step {
+            return Err(format!(
+                "min step {} is greater than max step {}",
+                policy.min_step, policy.max_step
+            ));
+        }
+        Ok(policy)
+    }
+
+    fn directions(&self) -> Vec<bool> {
+        let mut directions = Vec::new();
+        if self.increasing {
+            directions.push(true);
+        }
+        if self.decreasing {
+            directions.push(false);
         }
-        prev = curr;
+        directions
     }
-    true
+
+    fn is_safe_step(&self, prev: i32, curr: i32, increasing: bool) -> bool {
+        let diff = if increasing { curr - prev } else { prev - curr };
+        if diff == 0 {
+            self.allow_equal
+        } else {
+            (self.min_step..=self.max_step).contains(&diff)
+        }
+    }
+}
+
+// Equal neighbours are governed by `--allow-equal` alone, so a step of 0
+// would be silently ignored.
+fn parse_step(value: &str) -> Result<i32, String> {
+    match value.parse::<i32>() {
+        Ok(0) => Err("a step of 0 has no effect, use --allow-equal instead".to_string()),
+        Ok(x) if x > 0 => Ok(x),
+        _ => Err(format!("invalid step {}", value)),
+    }
+}
+
+fn is_safe(levels: Vec<i32>, policy: &SafetyPolicy) -> bool {
+    is_safe_with_dampener(levels, 0, policy)
+}
+
+fn is_safe_with_dampener(levels: Vec<i32>, max_fails: i32, policy: &SafetyPolicy) -> bool {
+    dampened_removals(&levels, max_fails as usize, policy).is_some()
+}
+
+fn dampened_removals(
+    levels: &[i32],
+    max_removals: usize,
+    policy: &SafetyPolicy,
+) -> Option<Vec<usize>> {
+    policy
+        .directions()
+        .into_iter()
+        .filter_map(|increasing| removals_for_direction(levels, max_removals, increasing, policy))
+        .min_by_key(|removed| removed.len())
+}
+
+fn removals_for_direction(
+    levels: &[i32],
+    max_removals: usize,
+    increasing: bool,
+    policy: &SafetyPolicy,
+) -> Option<Vec<usize>> {
+    let n = levels.len();
+    if n == 0 {
+        return Some(Vec::new());
+    }
+    // removed[i] = fewest removals before i that leave a valid run ending in levels[i]
+    let mut removed = vec![usize::MAX; n];
+    let mut prev: Vec<Option<usize>> = vec![None; n];
+    for i in 0..n {
+        if i <= max_removals {
+            removed[i] = i;
+        }
+        for j in i.saturating_sub(max_removals + 1)..i {
+            if removed[j] == usize::MAX {
+                continue;
+            }
+            let count = removed[j] + (i - j - 1);
+            if count < removed[i] && policy.is_safe_step(levels[j], levels[i], increasing) {
+                removed[i] = count;
+                prev[i] = Some(j);
+            }
+        }
+    }
+
+    let last = (0..n)
+        .filter(|&i| removed[i] != usize::MAX)
+        .min_by_key(|&i| removed[i] + (n - 1 - i))
+        .filter(|&i| removed[i] + (n - 1 - i) <= max_removals)?;
+    let mut kept = vec![false; n];
+    let mut curr = Some(last);
+    while let Some(i) = curr {
+        kept[i] = true;
+        curr = prev[i];
+    }
+    Some((0..n).filter(|&i| !kept[i]).collect())
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
     #[test]
     fn is_safe_increasing_true() {
-        let result = is_safe(vec![1, 3, 6, 7, 9]);
+        let result = is_safe(vec![1, 3, 6, 7, 9], &SafetyPolicy::default());
         assert_eq!(result, true);
     }
 
     #[test]
     fn is_safe_increasing_false() {
-        let result = is_safe(vec![1, 2, 7, 8, 9]);
+        let result = is_safe(vec![1, 2, 7, 8, 9], &SafetyPolicy::default());
         assert_eq!(result, false);
     }
 
     #[test]
     fn is_safe_decreasing_true() {
-        let result = is_safe(vec![7, 6, 4, 2, 1]);
+        let result = is_safe(vec![7, 6, 4, 2, 1], &SafetyPolicy::default());
         assert_eq!(result, true);
     }
 
     #[test]
     fn is_safe_decreasing_false() {
-        let result = is_safe(vec![9, 7, 6, 2, 1]);
+        let result = is_safe(vec![9, 7, 6, 2, 1], &SafetyPolicy::default());
         assert_eq!(result, false);
     }
 
     #[test]
     fn is_safe_with_dampener_increasing_true() {
-        let result = is_safe_with_dampener(vec![1, 3, 2, 4, 5], 1);
+        let result = is_safe_with_dampener(vec![1, 3, 2, 4, 5], 1, &SafetyPolicy::default());
         assert_eq!(result, true);
     }
 
     #[test]
     fn is_safe_with_dampener_increasing_true2() {
-        let result = is_safe_with_dampener(vec![48, 46, 47, 49, 51, 54, 56], 1);
+        let result = is_safe_with_dampener(
+            vec![48, 46, 47, 49, 51, 54, 56],
+            1,
+            &SafetyPolicy::default(),
+        );
         assert_eq!(result, true);
     }
 
     #[test]
     fn is_safe_with_dampener_increasing_false() {
-        let result = is_safe_with_dampener(vec![1, 2, 7, 8, 9], 1);
+        let result = is_safe_with_dampener(vec![1, 2, 7, 8, 9], 1, &SafetyPolicy::default());
         assert_eq!(result, false);
     }
 
     #[test]
     fn is_safe_with_dampener_decreasing_true() {
-        let result = is_safe_with_dampener(vec![8, 6, 4, 4, 1], 1);
+        let result = is_safe_with_dampener(vec![8, 6, 4, 4, 1], 1, &SafetyPolicy::default());
         assert_eq!(result, true);
     }
 
     #[test]
     fn is_safe_with_dampener_decreasing_false() {
-        let result = is_safe_with_dampener(vec![9, 7, 6, 2, 1], 1);
+        let result = is_safe_with_dampener(vec![9, 7, 6, 2, 1], 1, &SafetyPolicy::default());
         assert_eq!(result, false);
     }
+
+    #[test]
+    fn dampened_removals_indices() {
+        assert_eq!(
+            dampened_removals(&[1, 3, 2, 4, 5], 1, &SafetyPolicy::default()),
+            Some(vec![2])
+        );
+        assert_eq!(
+            dampened_removals(&[8, 6, 4, 4, 1], 1, &SafetyPolicy::default()),
+            Some(vec![3])
+        );
+        assert_eq!(
+            dampened_removals(&[7, 6, 4, 2, 1], 1, &SafetyPolicy::default()),
+            Some(vec![])
+        );
+        assert_eq!(
+            dampened_removals(&[1, 2, 7, 8, 9], 1, &SafetyPolicy::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn dampened_removals_first_pair_bad() {
+        assert_eq!(
+            dampened_removals(&[5, 1, 2, 3, 4], 1, &SafetyPolicy::default()),
+            Some(vec![0])
+        );
+        assert_eq!(
+            dampened_removals(&[1, 2, 3, 4, 9], 1, &SafetyPolicy::default()),
+            Some(vec![4])
+        );
+    }
+
+    #[test]
+    fn dampened_removals_k() {
+        let levels = [1, 9, 2, 9, 3, 4];
+        assert_eq!(
+            dampened_removals(&levels, 1, &SafetyPolicy::default()),
+            None
+        );
+        assert_eq!(
+            dampened_removals(&levels, 2, &SafetyPolicy::default()),
+            Some(vec![1, 3])
+        );
+        assert_eq!(
+            dampened_removals(&[4, 9], 2, &SafetyPolicy::default()),
+            Some(vec![1])
+        );
+        assert_eq!(
+            dampened_removals(&[4, 4, 4], 2, &SafetyPolicy::default()),
+            Some(vec![1, 2])
+        );
+    }
+
+    #[test]
+    fn safety_policy_from_args() {
+        let args = [
+            "--max-step",
+            "5",
+            "--direction",
+            "decreasing",
+            "--allow-equal",
+        ];
+        let policy = SafetyPolicy::from_args(args.iter().map(|x| x.to_string())).unwrap();
+        assert_eq!(
+            policy,
+            SafetyPolicy {
+                min_step: 1,
+                max_step: 5,
+                increasing: false,
+                decreasing: true,
+                allow_equal: true,
+            }
+        );
+        let args = ["--min-step", "4"];
+        assert!(SafetyPolicy::from_args(args.iter().map(|x| x.to_string())).is_err());
+        let args = ["--direction"];
+        assert!(SafetyPolicy::from_args(args.iter().map(|x| x.to_string())).is_err());
+        let args = ["--min-step", "0"];
+        let err = SafetyPolicy::from_args(args.iter().map(|x| x.to_string())).unwrap_err();
+        assert!(err.contains("--allow-equal"));
+    }
+
+    #[test]
+    fn is_safe_with_policy() {
+        let policy = SafetyPolicy {
+            max_step: 5,
+            decreasing: false,
+            allow_equal: true,
+            ..SafetyPolicy::default()
+        };
+        assert!(is_safe(vec![1, 1, 6, 7, 7, 9], &policy));
+        assert!(!is_safe(vec![9, 7, 6, 2, 1], &policy));
+        assert!(is_safe_with_dampener(vec![9, 1, 2, 3], 1, &policy));
+    }
+
+    #[test]
+    fn diagnose_unsafe() {
+        let result = diagnose(&[1, 2, 7, 8, 9], 1, &SafetyPolicy::default());
+        assert_eq!(
+            result,
+            Diagnosis {
+                violation: Some(Violation {
+                    index: 1,
+                    delta: 5,
+                    rule: Rule::StepTooLarge,
+                }),
+                removed: None,
+            }
+        );
+        assert_eq!(
+            result.to_text(2, &[1, 2, 7, 8, 9]),
+            "2: 1 2 7 8 9 unsafe, levels 1 and 2 (2 -> 7) delta +5 step too large"
+        );
+    }
+
+    #[test]
+    fn diagnose_dampened() {
+        let levels = [5, 1, 2, 3, 4];
+        let result = diagnose(&levels, 1, &SafetyPolicy::default());
+        assert_eq!(
+            result,
+            Diagnosis {
+                violation: Some(Violation {
+                    index: 0,
+                    delta: -4,
+                    rule: Rule::WrongDirection,
+                }),
+                removed: Some(vec![0]),
+            }
+        );
+        assert_eq!(
+            result.to_csv(3, &levels),
+            "3,5 1 2 3 4,dampened,0,-4,wrong direction,0"
+        );
+    }
+
+    #[test]
+    fn diagnose_safe() {
+        let levels = [7, 6, 4, 2, 1];
+        let result = diagnose(&levels, 1, &SafetyPolicy::default());
+        assert_eq!(result.status(), "safe");
+        assert_eq!(result.to_csv(1, &levels), "1,7 6 4 2 1,safe,,,,");
+    }
 }
@@ -1,84 +1,499 @@
 use std::fs::File;
 use std::io::{self, prelude::*, BufReader};
+use std::ops::RangeInclusive;
 
 fn main() -> io::Result<()> {
+    if std::env::args().nth(1).as_deref() == Some("extended") {
+        return extended().map(|sum| println!("{}", sum));
+    }
     part1().map(|sum| println!("{}", sum))?;
     part2().map(|sum| println!("{}", sum))
 }
 
-fn part1() -> io::Result<i32> {
+fn part1() -> io::Result<i64> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
 
-    let mut sum = 0;
-    for line in reader.lines() {
-        sum += parse_line(line?.as_str());
-    }
+    sum_mul(reader)
+}
 
-    Ok(sum)
+fn part2() -> io::Result<i64> {
+    let file = File::open("input.txt")?;
+    let reader = BufReader::new(file);
+
+    sum_mul_with_do_and_dont(reader)
 }
 
-fn part2() -> io::Result<i32> {
+fn extended() -> io::Result<i64> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
 
-    let text = reader
-        .lines()
-        .map(|x| x.unwrap())
-        .collect::<Vec<String>>()
-        .join("");
+    let m = InstructionSet::new()
+        .with(MUL_ANY)
+        .with(ADD)
+        .with(DO)
+        .with(DONT)
+        .with(TOGGLE)
+        .run_stream(reader)?;
+
+    Ok(m.acc)
+}
+
+fn sum_mul<R: BufRead>(reader: R) -> io::Result<i64> {
+    let m = InstructionSet::new().with(MUL).run_stream(reader)?;
+    Ok(m.acc)
+}
+
+fn sum_mul_with_do_and_dont<R: BufRead>(reader: R) -> io::Result<i64> {
+    let m = InstructionSet::new()
+        .with(MUL)
+        .with(DO)
+        .with(DONT)
+        .run_stream(reader)?;
+    Ok(m.acc)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Machine {
+    enabled: bool,
+    acc: i64,
+}
+
+impl Machine {
+    fn new() -> Self {
+        Machine {
+            enabled: true,
+            acc: 0,
+        }
+    }
+}
+
+struct Instruction {
+    name: &'static str,
+    arity: RangeInclusive<usize>,
+    max_digits: usize,
+    apply: fn(&mut Machine, &[i64]) -> Result<(), Overflow>,
+}
+
+const MUL: Instruction = Instruction {
+    name: "mul",
+    arity: 2..=2,
+    max_digits: 3,
+    apply: apply_mul,
+};
+
+// Bounds how long a call can be, so a stream never has to hold more than
+// one call's worth of bytes while waiting for it to close.
+const MAX_ARGS: usize = 64;
+
+const MUL_ANY: Instruction = Instruction {
+    name: "mul",
+    arity: 1..=MAX_ARGS,
+    max_digits: 3,
+    apply: apply_mul,
+};
+
+const ADD: Instruction = Instruction {
+    name: "add",
+    arity: 2..=2,
+    max_digits: 3,
+    apply: apply_add,
+};
+
+const DO: Instruction = Instruction {
+    name: "do",
+    arity: 0..=0,
+    max_digits: 0,
+    apply: |m, _| {
+        m.enabled = true;
+        Ok(())
+    },
+};
+
+const DONT: Instruction = Instruction {
+    name: "don't",
+    arity: 0..=0,
+    max_digits: 0,
+    apply: |m, _| {
+        m.enabled = false;
+        Ok(())
+    },
+};
 
-    let sum = parse_line_with_do_and_dont(text.as_str());
+const TOGGLE: Instruction = Instruction {
+    name: "toggle",
+    arity: 0..=0,
+    max_digits: 0,
+    apply: |m, _| {
+        m.enabled = !m.enabled;
+        Ok(())
+    },
+};
 
-    Ok(sum)
+/// The call's result does not fit the accumulator; `mul` with any number of
+/// arguments reaches that quickly.
+struct Overflow;
+
+fn apply_mul(m: &mut Machine, args: &[i64]) -> Result<(), Overflow> {
+    if m.enabled {
+        let product = args.iter().try_fold(1i64, |p, x| p.checked_mul(*x));
+        m.acc = product.and_then(|x| m.acc.checked_add(x)).ok_or(Overflow)?;
+    }
+    Ok(())
 }
 
-fn parse_line(line: &str) -> i32 {
-    line.split("mul(")
-        .map(|str| str.split(")").next().unwrap())
-        .map(|str| str.split(","))
-        .filter_map(|v| parse_mul(v.collect()))
-        .sum()
+fn apply_add(m: &mut Machine, args: &[i64]) -> Result<(), Overflow> {
+    if m.enabled {
+        let sum = args.iter().try_fold(0i64, |s, x| s.checked_add(*x));
+        m.acc = sum.and_then(|x| m.acc.checked_add(x)).ok_or(Overflow)?;
+    }
+    Ok(())
 }
 
-fn parse_line_with_do_and_dont(line: &str) -> i32 {
-    let parts: Vec<&str> = line.split("don't()").collect();
-    let mut text = parts[0].to_owned();
-    for i in 1..parts.len() {
-        let mut tmp = parts[i].split("do()").collect::<Vec<&str>>();
-        tmp.remove(0);
-        text += tmp.join("").as_str();
+impl Instruction {
+    fn max_len(&self) -> usize {
+        let args = *self.arity.end() * (self.max_digits + 1);
+        self.name.len() + 1 + args.max(1)
     }
-    parse_line(text.as_str())
 }
 
-fn parse_mul(parts: Vec<&str>) -> Option<i32> {
-    if parts.len() == 2 {
-        match (parts[0].parse::<i32>(), parts[1].parse::<i32>()) {
-            (Ok(a), Ok(b)) => Option::Some(a * b),
-            _ => Option::None,
+struct InstructionSet {
+    instructions: Vec<Instruction>,
+}
+
+impl InstructionSet {
+    fn new() -> Self {
+        InstructionSet {
+            instructions: Vec::new(),
+        }
+    }
+
+    fn with(mut self, instruction: Instruction) -> Self {
+        self.instructions.retain(|x| x.name != instruction.name);
+        self.instructions.push(instruction);
+        self
+    }
+
+    fn max_len(&self) -> usize {
+        self.instructions
+            .iter()
+            .map(Instruction::max_len)
+            .max()
+            .unwrap_or(1)
+    }
+
+    fn get(&self, name: &str) -> Option<&Instruction> {
+        self.instructions.iter().find(|x| x.name == name)
+    }
+
+    fn run_stream<R: BufRead>(&self, mut reader: R) -> io::Result<Machine> {
+        let mut m = Machine::new();
+        let mut pending: Vec<u8> = Vec::new();
+        let mut offset = 0;
+        loop {
+            let chunk = reader.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+            let len = chunk.len();
+            pending.extend_from_slice(chunk);
+            reader.consume(len);
+            let consumed = self.feed(&mut m, &pending, false, offset)?;
+            pending.drain(..consumed);
+            offset += consumed;
+            debug_assert!(pending.len() < self.max_len());
+        }
+        self.feed(&mut m, &pending, true, offset)?;
+        Ok(m)
+    }
+
+    /// `offset` is where `bytes` starts in the stream, for error messages.
+    fn feed(&self, m: &mut Machine, bytes: &[u8], eof: bool, offset: usize) -> io::Result<usize> {
+        let mut lexer = Lexer::new(self, bytes, eof);
+        for (start, token) in lexer.by_ref() {
+            let instruction = self.get(token.name).unwrap();
+            (instruction.apply)(m, &token.args).map_err(|Overflow| {
+                let args = token.args.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "byte {}: {}({}) overflows the total",
+                        offset + start,
+                        token.name,
+                        args.join(",")
+                    ),
+                )
+            })?;
         }
-    } else {
-        Option::None
+        Ok(lexer.pos)
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Token {
+    name: &'static str,
+    args: Vec<i64>,
+}
+
+enum Scan<T> {
+    Found(T, usize),
+    Missing,
+    Incomplete,
+}
+
+struct Lexer<'a> {
+    set: &'a InstructionSet,
+    bytes: &'a [u8],
+    pos: usize,
+    eof: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(set: &'a InstructionSet, bytes: &'a [u8], eof: bool) -> Self {
+        Lexer {
+            set,
+            bytes,
+            pos: 0,
+            eof,
+        }
+    }
+
+    fn token_at(&self, start: usize) -> Scan<Token> {
+        let rest = &self.bytes[start..];
+        for instruction in self.set.instructions.iter() {
+            match call_at(instruction, rest) {
+                Scan::Found(token, len) => return Scan::Found(token, len),
+                // Nothing longer than `max_len` can still complete.
+                Scan::Incomplete if !self.eof && rest.len() < instruction.max_len() => {
+                    return Scan::Incomplete
+                }
+                Scan::Incomplete | Scan::Missing => {}
+            }
+        }
+        Scan::Missing
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = (usize, Token);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.bytes.len() {
+            let start = self.pos;
+            match self.token_at(start) {
+                Scan::Found(token, len) => {
+                    self.pos += len;
+                    return Some((start, token));
+                }
+                Scan::Missing => self.pos += 1,
+                Scan::Incomplete => return None,
+            }
+        }
+        None
+    }
+}
+
+fn call_at(instruction: &Instruction, bytes: &[u8]) -> Scan<Token> {
+    let head = instruction.name.bytes().chain(Some(b'('));
+    if !head.zip(bytes).all(|(a, b)| a == *b) {
+        return Scan::Missing;
+    }
+    let mut len = instruction.name.len() + 1;
+    let mut args = Vec::new();
+    match bytes.get(len) {
+        None => return Scan::Incomplete,
+        Some(b')') => {}
+        Some(_) => loop {
+            match number_at(&bytes[len..], instruction.max_digits) {
+                Scan::Found(n, n_len) => {
+                    args.push(n);
+                    len += n_len;
+                }
+                Scan::Missing => return Scan::Missing,
+                Scan::Incomplete => return Scan::Incomplete,
+            }
+            if args.len() > *instruction.arity.end() {
+                return Scan::Missing;
+            }
+            match bytes.get(len) {
+                Some(b',') => len += 1,
+                Some(b')') => break,
+                Some(_) => return Scan::Missing,
+                None => return Scan::Incomplete,
+            }
+        },
+    }
+    if !instruction.arity.contains(&args.len()) {
+        return Scan::Missing;
+    }
+    let token = Token {
+        name: instruction.name,
+        args,
+    };
+    Scan::Found(token, len + 1)
+}
+
+fn number_at(bytes: &[u8], max_digits: usize) -> Scan<i64> {
+    let len = bytes
+        .iter()
+        .take(max_digits)
+        .take_while(|x| x.is_ascii_digit())
+        .count();
+    if len == bytes.len() && len < max_digits {
+        return Scan::Incomplete;
+    }
+    if len == 0 {
+        return Scan::Missing;
+    }
+    let n = bytes[..len]
+        .iter()
+        .fold(0, |n, x| n * 10 + (x - b'0') as i64);
+    Scan::Found(n, len)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn parse_line_test() {
-        let result =
-            parse_line("xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))");
+    fn sum_mul_test() {
+        let result = sum_mul(
+            "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))".as_bytes(),
+        )
+        .unwrap();
         assert_eq!(result, 161);
     }
 
     #[test]
-    fn parse_line_with_do_and_dont_test() {
-        let result = parse_line_with_do_and_dont(
-            "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))",
-        );
+    fn sum_mul_with_do_and_dont_test() {
+        let result = sum_mul_with_do_and_dont(
+            "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))".as_bytes(),
+        )
+        .unwrap();
         assert_eq!(result, 48);
     }
+
+    fn token(name: &'static str, args: &[i64]) -> Token {
+        Token {
+            name,
+            args: args.to_vec(),
+        }
+    }
+
+    #[test]
+    fn lexer_test() {
+        let set = InstructionSet::new().with(MUL).with(DO).with(DONT);
+        let result = Lexer::new(&set, b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+do()", true)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                (1, token("mul", &[2, 4])),
+                (20, token("don't", &[])),
+                (28, token("mul", &[5, 5])),
+                (37, token("do", &[])),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexer_exact_grammar_test() {
+        let set = InstructionSet::new().with(MUL).with(DO).with(DONT);
+        let result = Lexer::new(
+            &set,
+            b"mul(1234,5)mul(-1,2)mul( 1,2)mul(+1,2)mul(1,2 )mul(1,2,3)do( )mul(mul(12,345)",
+            true,
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(result, vec![(66, token("mul", &[12, 345]))]);
+    }
+
+    #[test]
+    fn extended_instruction_set_test() {
+        let set = InstructionSet::new()
+            .with(MUL_ANY)
+            .with(ADD)
+            .with(TOGGLE)
+            .with(DONT);
+        let result = set
+            .run_stream(
+                "mul(2,3,4)add(1,2)toggle()mul(5,5)toggle()mul(7)don't()add(9,9)".as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            Machine {
+                enabled: false,
+                acc: 24 + 3 + 7,
+            }
+        );
+    }
+
+    #[test]
+    fn max_len_test() {
+        assert_eq!(MUL.max_len(), "mul(123,456)".len());
+        assert_eq!(DONT.max_len(), "don't()".len());
+        assert_eq!(MUL_ANY.max_len(), 4 + MAX_ARGS * 4);
+    }
+
+    #[test]
+    fn run_stream_unterminated_test() {
+        let set = InstructionSet::new().with(MUL_ANY).with(DO).with(DONT);
+        let mut text = "mul(2,3)don't()".to_string();
+        text += &"mul(".repeat(3);
+        text += &"1,".repeat(MAX_ARGS * 3);
+        text += "do()mul(";
+        text += &"1,".repeat(MAX_ARGS - 1);
+        text += "7)mul(4)";
+        let expected = set.run_stream(text.as_bytes()).unwrap();
+        assert_eq!(
+            expected,
+            Machine {
+                enabled: true,
+                acc: 6 + 7 + 4,
+            }
+        );
+        for capacity in 1..16 {
+            let reader = BufReader::with_capacity(capacity, text.as_bytes());
+            assert_eq!(set.run_stream(reader).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn mul_overflow_test() {
+        let set = InstructionSet::new().with(MUL_ANY);
+        let text = "mul(2,3)mul(999,999,999,999,999,999,999)mul(4)";
+        for capacity in [1, 5, 64] {
+            let reader = BufReader::with_capacity(capacity, text.as_bytes());
+            let err = set.run_stream(reader).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(
+                err.to_string(),
+                "byte 8: mul(999,999,999,999,999,999,999) overflows the total"
+            );
+        }
+
+        let text = "mul(999,999,999,999,999,999)".repeat(10);
+        let err = set.run_stream(text.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "byte {}: mul(999,999,999,999,999,999) overflows the total",
+                9 * 28
+            )
+        );
+    }
+
+    #[test]
+    fn run_stream_test() {
+        let text = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))\n\
+                    mul(123,4)don't()\nmul(1,1)do()\nmul(2,2)";
+        let set = InstructionSet::new().with(MUL).with(DO).with(DONT);
+        let expected = set.run_stream(text.as_bytes()).unwrap();
+        assert_eq!(expected.acc, 48 + 492 + 4);
+        for capacity in 1..16 {
+            let reader = BufReader::with_capacity(capacity, text.as_bytes());
+            assert_eq!(set.run_stream(reader).unwrap(), expected);
+        }
+    }
 }
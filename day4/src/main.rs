@@ -1,7 +1,22 @@
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::{self, prelude::*, BufReader};
 
 fn main() -> io::Result<()> {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(|x| x.as_str()) == Some("render") {
+        let colour = args.iter().any(|x| x == "--colour");
+        let x_mas = args.iter().any(|x| x == "x-mas");
+        return render(x_mas, colour).map(|grid| print!("{}", grid));
+    }
+    if args.get(1).map(|x| x.as_str()) == Some("words") {
+        let path = args.get(2).map(|x| x.as_str()).unwrap_or("words.txt");
+        return words(path).map(|counts| {
+            for (word, count) in counts {
+                println!("{} {}", word, count);
+            }
+        });
+    }
     part1().map(|count| println!("{}", count))?;
     part2().map(|count| println!("{}", count))
 }
@@ -34,277 +49,488 @@ fn part2() -> io::Result<usize> {
     Ok(count)
 }
 
-#[derive(Debug, Copy, Clone)]
-enum XMAS {
-    X,
-    M,
-    A,
-    S,
+fn render(x_mas: bool, colour: bool) -> io::Result<String> {
+    let file = File::open("input.txt")?;
+    let reader = BufReader::new(file);
+
+    let text = reader
+        .lines()
+        .map(|x| x.unwrap())
+        .collect::<Vec<String>>()
+        .join("\n");
+    let grid = if x_mas {
+        render_mas(text.trim(), colour)
+    } else {
+        render_xmas(text.trim(), colour)
+    };
+
+    Ok(grid)
 }
 
-fn count_xmas(text: &str) -> usize {
-    let table = parse_text(text);
-    count_horizontal(&table)
-        + count_horizontal_rev(&table)
-        + count_vertical(&table)
-        + count_vertical_rev(&table)
-        + count_diagonal_l_to_r(&table)
-        + count_diagonal_l_to_r_rev(&table)
-        + count_diagonal_r_to_l(&table)
-        + count_diagonal_r_to_l_rev(&table)
+fn words(path: &str) -> io::Result<Vec<(String, usize)>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let words = reader
+        .lines()
+        .map(|x| x.map(|w| w.trim().to_owned()))
+        .filter(|x| x.as_ref().map_or(true, |w| !w.is_empty()))
+        .collect::<io::Result<Vec<String>>>()?;
+
+    let file = File::open("input.txt")?;
+    let reader = BufReader::new(file);
+    let text = reader
+        .lines()
+        .map(|x| x.unwrap())
+        .collect::<Vec<String>>()
+        .join("\n");
+    let table = parse_text(text.trim());
+
+    let word_refs = words.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    let counts = count_words(&table, &word_refs, true, true);
+
+    Ok(words.into_iter().zip(counts).collect())
 }
 
-fn count_mas(text: &str) -> usize {
-    let table = parse_text(text);
-    count_mas_top(&table)
-        + count_mas_right(&table)
-        + count_mas_bottom(&table)
-        + count_mas_left(&table)
+#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone)]
+struct Pos {
+    row: usize,
+    col: usize,
 }
 
-fn count_mas_top(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 2 {
-        for col in 0..table[0].len() - 2 {
-            match (
-                table[row][col],
-                table[row][col + 2],
-                table[row + 1][col + 1],
-                table[row + 2][col],
-                table[row + 2][col + 2],
-            ) {
-                (XMAS::M, XMAS::M, XMAS::A, XMAS::S, XMAS::S) => count += 1,
-                _ => (),
-            }
+#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone)]
+struct Dir {
+    d_row: i32,
+    d_col: i32,
+}
+
+impl Dir {
+    const RIGHT: Dir = Dir { d_row: 0, d_col: 1 };
+    const DOWN: Dir = Dir { d_row: 1, d_col: 0 };
+    const DOWN_RIGHT: Dir = Dir { d_row: 1, d_col: 1 };
+    const DOWN_LEFT: Dir = Dir {
+        d_row: 1,
+        d_col: -1,
+    };
+
+    fn rev(&self) -> Dir {
+        Dir {
+            d_row: -self.d_row,
+            d_col: -self.d_col,
         }
     }
-    count
 }
 
-fn count_mas_bottom(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 2 {
-        for col in 0..table[0].len() - 2 {
-            match (
-                table[row][col],
-                table[row][col + 2],
-                table[row + 1][col + 1],
-                table[row + 2][col],
-                table[row + 2][col + 2],
-            ) {
-                (XMAS::S, XMAS::S, XMAS::A, XMAS::M, XMAS::M) => count += 1,
-                _ => (),
-            }
-        }
+fn directions(diagonal: bool, reverse: bool) -> Vec<Dir> {
+    let mut dirs = vec![Dir::RIGHT, Dir::DOWN];
+    if diagonal {
+        dirs.extend([Dir::DOWN_RIGHT, Dir::DOWN_LEFT]);
     }
-    count
+    if reverse {
+        let rev = dirs.iter().map(|d| d.rev()).collect::<Vec<_>>();
+        dirs.extend(rev);
+    }
+    dirs
+}
+
+#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone)]
+struct WordMatch {
+    word: usize,
+    start: Pos,
+    dir: Dir,
 }
 
-fn count_mas_right(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 2 {
-        for col in 0..table[0].len() - 2 {
-            match (
-                table[row][col],
-                table[row][col + 2],
-                table[row + 1][col + 1],
-                table[row + 2][col],
-                table[row + 2][col + 2],
-            ) {
-                (XMAS::S, XMAS::M, XMAS::A, XMAS::S, XMAS::M) => count += 1,
-                _ => (),
+fn search_words(table: &[Vec<char>], words: &[&str], dirs: &[Dir]) -> Vec<WordMatch> {
+    let words = words
+        .iter()
+        .map(|w| w.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let mut matches = Vec::new();
+    for row in 0..table.len() {
+        for col in 0..table[row].len() {
+            let start = Pos { row, col };
+            for (word_idx, word) in words.iter().enumerate() {
+                for dir in dirs {
+                    if word_at(table, word, start, *dir) {
+                        matches.push(WordMatch {
+                            word: word_idx,
+                            start,
+                            dir: *dir,
+                        });
+                    }
+                }
             }
         }
     }
-    count
+    matches
 }
 
-fn count_mas_left(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 2 {
-        for col in 0..table[0].len() - 2 {
-            match (
-                table[row][col],
-                table[row][col + 2],
-                table[row + 1][col + 1],
-                table[row + 2][col],
-                table[row + 2][col + 2],
-            ) {
-                (XMAS::M, XMAS::S, XMAS::A, XMAS::M, XMAS::S) => count += 1,
-                _ => (),
-            }
-        }
+fn word_at(table: &[Vec<char>], word: &[char], start: Pos, dir: Dir) -> bool {
+    word.iter().enumerate().all(|(i, ch)| {
+        step(start, dir, i)
+            .and_then(|p| table.get(p.row).and_then(|r| r.get(p.col)))
+            .is_some_and(|x| x == ch)
+    })
+}
+
+fn step(start: Pos, dir: Dir, n: usize) -> Option<Pos> {
+    let row = start.row as i64 + dir.d_row as i64 * n as i64;
+    let col = start.col as i64 + dir.d_col as i64 * n as i64;
+    if row < 0 || col < 0 {
+        return None;
     }
-    count
+    Some(Pos {
+        row: row as usize,
+        col: col as usize,
+    })
 }
 
-fn count_horizontal(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() {
-        for col in 0..table[0].len() - 3 {
-            match (
-                table[row][col],
-                table[row][col + 1],
-                table[row][col + 2],
-                table[row][col + 3],
-            ) {
-                (XMAS::X, XMAS::M, XMAS::A, XMAS::S) => count += 1,
-                _ => (),
-            }
-        }
+fn count_words(table: &[Vec<char>], words: &[&str], diagonal: bool, reverse: bool) -> Vec<usize> {
+    let mut patterns = words
+        .iter()
+        .map(|w| w.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    if reverse {
+        let reversed = patterns
+            .iter()
+            .map(|w| w.iter().rev().copied().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        patterns.extend(reversed);
+    }
+    let automaton = AhoCorasick::new(&patterns);
+
+    let mut counts = vec![0; patterns.len()];
+    for line in grid_lines(table, diagonal) {
+        automaton.count(&line, &mut counts);
     }
-    count
+    (0..words.len())
+        .map(|i| counts[i] + if reverse { counts[i + words.len()] } else { 0 })
+        .collect()
 }
 
-fn count_horizontal_rev(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() {
-        for col in 0..table[0].len() - 3 {
-            match (
-                table[row][col],
-                table[row][col + 1],
-                table[row][col + 2],
-                table[row][col + 3],
-            ) {
-                (XMAS::S, XMAS::A, XMAS::M, XMAS::X) => count += 1,
-                _ => (),
+fn grid_lines(table: &[Vec<char>], diagonal: bool) -> Vec<Vec<char>> {
+    let height = table.len() as i32;
+    let width = table.iter().map(|r| r.len()).max().unwrap_or(0) as i32;
+    let mut starts: Vec<(i32, i32, Dir)> = Vec::new();
+    starts.extend((0..height).map(|row| (row, 0, Dir::RIGHT)));
+    starts.extend((0..width).map(|col| (0, col, Dir::DOWN)));
+    if diagonal {
+        starts.extend((0..height).map(|row| (row, 0, Dir::DOWN_RIGHT)));
+        starts.extend((1..width).map(|col| (0, col, Dir::DOWN_RIGHT)));
+        starts.extend((0..height).map(|row| (row, width - 1, Dir::DOWN_LEFT)));
+        starts.extend((0..width - 1).map(|col| (0, col, Dir::DOWN_LEFT)));
+    }
+
+    let mut lines = Vec::new();
+    for (mut row, mut col, dir) in starts {
+        let mut cells = Vec::new();
+        while (0..height).contains(&row) && (0..width).contains(&col) {
+            cells.push(table[row as usize].get(col as usize).copied());
+            row += dir.d_row;
+            col += dir.d_col;
+        }
+        // ragged rows split a line where a cell is missing
+        for part in cells.split(|x| x.is_none()) {
+            if !part.is_empty() {
+                lines.push(part.iter().flatten().copied().collect());
             }
         }
     }
-    count
+    lines
+}
+
+struct AhoCorasick {
+    next: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    out: Vec<Vec<usize>>,
 }
 
-fn count_vertical(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 3 {
-        for col in 0..table[0].len() {
-            match (
-                table[row][col],
-                table[row + 1][col],
-                table[row + 2][col],
-                table[row + 3][col],
-            ) {
-                (XMAS::X, XMAS::M, XMAS::A, XMAS::S) => count += 1,
-                _ => (),
+impl AhoCorasick {
+    fn new(patterns: &[Vec<char>]) -> Self {
+        let mut ac = AhoCorasick {
+            next: vec![HashMap::new()],
+            fail: vec![0],
+            out: vec![Vec::new()],
+        };
+        for (i, pattern) in patterns.iter().enumerate() {
+            if pattern.is_empty() {
+                continue;
+            }
+            let mut node = 0;
+            for ch in pattern {
+                node = match ac.next[node].get(ch) {
+                    Some(n) => *n,
+                    None => {
+                        ac.next.push(HashMap::new());
+                        ac.fail.push(0);
+                        ac.out.push(Vec::new());
+                        let n = ac.next.len() - 1;
+                        ac.next[node].insert(*ch, n);
+                        n
+                    }
+                };
             }
+            ac.out[node].push(i);
         }
-    }
-    count
-}
 
-fn count_vertical_rev(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 3 {
-        for col in 0..table[0].len() {
-            match (
-                table[row][col],
-                table[row + 1][col],
-                table[row + 2][col],
-                table[row + 3][col],
-            ) {
-                (XMAS::S, XMAS::A, XMAS::M, XMAS::X) => count += 1,
-                _ => (),
+        let mut queue = ac.next[0].values().copied().collect::<VecDeque<_>>();
+        while let Some(node) = queue.pop_front() {
+            let edges = ac.next[node]
+                .iter()
+                .map(|(ch, n)| (*ch, *n))
+                .collect::<Vec<_>>();
+            for (ch, child) in edges {
+                let mut f = ac.fail[node];
+                while f != 0 && !ac.next[f].contains_key(&ch) {
+                    f = ac.fail[f];
+                }
+                let target = ac.next[f].get(&ch).copied().unwrap_or(0);
+                ac.fail[child] = if target == child { 0 } else { target };
+                let inherited = ac.out[ac.fail[child]].clone();
+                ac.out[child].extend(inherited);
+                queue.push_back(child);
             }
         }
+        ac
     }
-    count
-}
 
-fn count_diagonal_l_to_r(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 3 {
-        for col in 0..table[0].len() - 3 {
-            match (
-                table[row][col],
-                table[row + 1][col + 1],
-                table[row + 2][col + 2],
-                table[row + 3][col + 3],
-            ) {
-                (XMAS::X, XMAS::M, XMAS::A, XMAS::S) => count += 1,
-                _ => (),
+    fn count(&self, text: &[char], counts: &mut [usize]) {
+        let mut node = 0;
+        for ch in text {
+            while node != 0 && !self.next[node].contains_key(ch) {
+                node = self.fail[node];
+            }
+            node = self.next[node].get(ch).copied().unwrap_or(0);
+            for i in &self.out[node] {
+                counts[*i] += 1;
             }
         }
     }
-    count
 }
 
-fn count_diagonal_l_to_r_rev(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 3 {
-        for col in 0..table[0].len() - 3 {
-            match (
-                table[row][col],
-                table[row + 1][col + 1],
-                table[row + 2][col + 2],
-                table[row + 3][col + 3],
-            ) {
-                (XMAS::S, XMAS::A, XMAS::M, XMAS::X) => count += 1,
-                _ => (),
+fn count_xmas(text: &str) -> usize {
+    let table = parse_text(text);
+    search_words(&table, &["XMAS"], &directions(true, true)).len()
+}
+
+fn count_mas(text: &str) -> usize {
+    let table = parse_text(text);
+    let template = Template::parse(
+        "M.S
+.A.
+M.S",
+    );
+    match_template(&table, &template).len()
+}
+
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+struct Template {
+    cells: Vec<Vec<Option<char>>>,
+}
+
+impl Template {
+    /// Short rows are padded with wildcards, so every template is a
+    /// rectangle and its rotations compare equal to it where they should.
+    fn parse(text: &str) -> Self {
+        let mut cells = text
+            .lines()
+            .map(|line| {
+                line.trim()
+                    .chars()
+                    .map(|ch| if ch == '.' { None } else { Some(ch) })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let width = cells.iter().map(|r| r.len()).max().unwrap_or(0);
+        for row in cells.iter_mut() {
+            row.resize(width, None);
+        }
+        Template { cells }
+    }
+
+    fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn width(&self) -> usize {
+        self.cells.iter().map(|r| r.len()).max().unwrap_or(0)
+    }
+
+    fn get(&self, row: usize, col: usize) -> Option<char> {
+        self.cells
+            .get(row)
+            .and_then(|r| r.get(col))
+            .copied()
+            .flatten()
+    }
+
+    fn rotate(&self) -> Self {
+        let (height, width) = (self.height(), self.width());
+        let cells = (0..width)
+            .map(|row| {
+                (0..height)
+                    .map(|col| self.get(height - 1 - col, row))
+                    .collect()
+            })
+            .collect();
+        Template { cells }
+    }
+
+    fn reflect(&self) -> Self {
+        let width = self.width();
+        let cells = (0..self.height())
+            .map(|row| {
+                (0..width)
+                    .map(|col| self.get(row, width - 1 - col))
+                    .collect()
+            })
+            .collect();
+        Template { cells }
+    }
+
+    fn cells_at(&self, start: Pos) -> Vec<Pos> {
+        let mut cells = Vec::new();
+        for (row, line) in self.cells.iter().enumerate() {
+            for (col, cell) in line.iter().enumerate() {
+                if cell.is_some() {
+                    cells.push(Pos {
+                        row: start.row + row,
+                        col: start.col + col,
+                    });
+                }
             }
         }
+        cells
     }
-    count
-}
 
-fn count_diagonal_r_to_l(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 3 {
-        for col in 0..table[0].len() - 3 {
-            match (
-                table[row][col + 3],
-                table[row + 1][col + 2],
-                table[row + 2][col + 1],
-                table[row + 3][col],
-            ) {
-                (XMAS::X, XMAS::M, XMAS::A, XMAS::S) => count += 1,
-                _ => (),
+    fn variants(&self) -> Vec<Template> {
+        let mut variants: Vec<Template> = Vec::new();
+        for start in [self.clone(), self.reflect()] {
+            let mut curr = start;
+            for _ in 0..4 {
+                let next = curr.rotate();
+                if !variants.contains(&curr) {
+                    variants.push(curr);
+                }
+                curr = next;
             }
         }
+        variants
     }
-    count
 }
 
-fn count_diagonal_r_to_l_rev(table: &Vec<Vec<XMAS>>) -> usize {
-    let mut count: usize = 0;
-    for row in 0..table.len() - 3 {
-        for col in 0..table[0].len() - 3 {
-            match (
-                table[row][col + 3],
-                table[row + 1][col + 2],
-                table[row + 2][col + 1],
-                table[row + 3][col],
-            ) {
-                (XMAS::S, XMAS::A, XMAS::M, XMAS::X) => count += 1,
-                _ => (),
+#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone)]
+struct TemplateMatch {
+    variant: usize,
+    start: Pos,
+}
+
+fn match_template(table: &[Vec<char>], template: &Template) -> Vec<TemplateMatch> {
+    template
+        .variants()
+        .iter()
+        .enumerate()
+        .flat_map(|(variant, t)| {
+            match_exact(table, t)
+                .into_iter()
+                .map(move |start| TemplateMatch { variant, start })
+        })
+        .collect()
+}
+
+fn match_exact(table: &[Vec<char>], template: &Template) -> Vec<Pos> {
+    let (height, width) = (template.height(), template.width());
+    let mut matches = Vec::new();
+    for row in 0..table.len() {
+        for col in 0..table[row].len() {
+            // wildcards too must lie on the grid
+            let fits = row + height <= table.len()
+                && table[row..row + height]
+                    .iter()
+                    .all(|r| col + width <= r.len());
+            let found = fits
+                && template.cells.iter().enumerate().all(|(t_row, cells)| {
+                    cells.iter().enumerate().all(|(t_col, cell)| match cell {
+                        None => true,
+                        Some(ch) => table
+                            .get(row + t_row)
+                            .and_then(|r| r.get(col + t_col))
+                            .is_some_and(|x| x == ch),
+                    })
+                });
+            if found {
+                matches.push(Pos { row, col });
             }
         }
     }
-    count
+    matches
+}
+
+fn render_xmas(text: &str, colour: bool) -> String {
+    let table = parse_text(text);
+    let matches = search_words(&table, &["XMAS"], &directions(true, true))
+        .iter()
+        .map(|m| (0..4).filter_map(|i| step(m.start, m.dir, i)).collect())
+        .collect::<Vec<_>>();
+    render_matches(&table, &matches, colour)
 }
 
-fn parse_text(text: &str) -> Vec<Vec<XMAS>> {
-    let mut table: Vec<Vec<XMAS>> = Vec::new();
-
-    for line in text.lines() {
-        let mut tmp: Vec<XMAS> = Vec::new();
-        for ch in line.chars() {
-            match ch {
-                'X' => tmp.push(XMAS::X),
-                'M' => tmp.push(XMAS::M),
-                'A' => tmp.push(XMAS::A),
-                'S' => tmp.push(XMAS::S),
-                _ => panic!("unknown char {ch}"),
-            };
+fn render_mas(text: &str, colour: bool) -> String {
+    let table = parse_text(text);
+    let template = Template::parse(
+        "M.S
+.A.
+M.S",
+    );
+    let variants = template.variants();
+    let matches = match_template(&table, &template)
+        .iter()
+        .map(|m| variants[m.variant].cells_at(m.start))
+        .collect::<Vec<_>>();
+    render_matches(&table, &matches, colour)
+}
+
+const COLOURS: [&str; 6] = ["31", "32", "33", "34", "35", "36"];
+
+fn render_matches(table: &[Vec<char>], matches: &[Vec<Pos>], colour: bool) -> String {
+    let mut owner: HashMap<Pos, usize> = HashMap::new();
+    for (i, cells) in matches.iter().enumerate() {
+        for pos in cells {
+            owner.entry(*pos).or_insert(i);
         }
-        table.push(tmp);
     }
-    table
+    let mut grid = String::new();
+    for (row, line) in table.iter().enumerate() {
+        for (col, ch) in line.iter().enumerate() {
+            match owner.get(&Pos { row, col }) {
+                Some(i) if colour => {
+                    let code = COLOURS[i % COLOURS.len()];
+                    grid += format!("\x1b[{}m{}\x1b[0m", code, ch).as_str();
+                }
+                Some(_) => grid.push(*ch),
+                None => grid.push('.'),
+            }
+        }
+        grid.push('\n');
+    }
+    grid
+}
+
+fn parse_text(text: &str) -> Vec<Vec<char>> {
+    text.lines().map(|line| line.chars().collect()).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn count_exact(template: &str, table: &[Vec<char>]) -> usize {
+        match_exact(table, &Template::parse(template)).len()
+    }
+
+    fn count_in_dirs(dirs: &[Dir], table: &[Vec<char>]) -> usize {
+        search_words(table, &["XMAS"], dirs).len()
+    }
+
     #[test]
     fn count_xmas_test() {
         let result = count_xmas(
@@ -324,133 +550,169 @@ MXMXAXMASX",
 
     #[test]
     fn count_horizontal_test() {
-        let result = count_horizontal(&vec![
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::S, XMAS::A, XMAS::M, XMAS::X],
-        ]);
+        let result = count_in_dirs(
+            &[Dir::RIGHT],
+            &[
+                vec!['X', 'M', 'A', 'S'],
+                vec!['X', 'M', 'A', 'S'],
+                vec!['S', 'A', 'M', 'X'],
+            ],
+        );
         assert_eq!(result, 2);
     }
 
     #[test]
     fn count_horizontal_rev_test() {
-        let result = count_horizontal_rev(&vec![
-            vec![XMAS::S, XMAS::A, XMAS::M, XMAS::X],
-            vec![XMAS::S, XMAS::A, XMAS::M, XMAS::X],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-        ]);
+        let result = count_in_dirs(
+            &[Dir::RIGHT.rev()],
+            &[
+                vec!['S', 'A', 'M', 'X'],
+                vec!['S', 'A', 'M', 'X'],
+                vec!['X', 'M', 'A', 'S'],
+            ],
+        );
         assert_eq!(result, 2);
     }
 
     #[test]
     fn count_vertical_test() {
-        let result = count_vertical(&vec![
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::X],
-            vec![XMAS::M, XMAS::X, XMAS::A, XMAS::X],
-            vec![XMAS::A, XMAS::M, XMAS::A, XMAS::M],
-            vec![XMAS::S, XMAS::A, XMAS::A, XMAS::A],
-            vec![XMAS::X, XMAS::S, XMAS::A, XMAS::S],
-        ]);
+        let result = count_in_dirs(
+            &[Dir::DOWN],
+            &[
+                vec!['X', 'M', 'A', 'X'],
+                vec!['M', 'X', 'A', 'X'],
+                vec!['A', 'M', 'A', 'M'],
+                vec!['S', 'A', 'A', 'A'],
+                vec!['X', 'S', 'A', 'S'],
+            ],
+        );
         assert_eq!(result, 3);
     }
 
     #[test]
     fn count_vertical_rev_test() {
-        let result = count_vertical_rev(&vec![
-            vec![XMAS::X, XMAS::S, XMAS::A, XMAS::X],
-            vec![XMAS::S, XMAS::A, XMAS::A, XMAS::S],
-            vec![XMAS::A, XMAS::M, XMAS::A, XMAS::A],
-            vec![XMAS::M, XMAS::X, XMAS::A, XMAS::M],
-            vec![XMAS::X, XMAS::S, XMAS::A, XMAS::X],
-        ]);
+        let result = count_in_dirs(
+            &[Dir::DOWN.rev()],
+            &[
+                vec!['X', 'S', 'A', 'X'],
+                vec!['S', 'A', 'A', 'S'],
+                vec!['A', 'M', 'A', 'A'],
+                vec!['M', 'X', 'A', 'M'],
+                vec!['X', 'S', 'A', 'X'],
+            ],
+        );
         assert_eq!(result, 3);
     }
 
     #[test]
     fn count_diagonal_l_to_r_test() {
-        let result = count_diagonal_l_to_r(&vec![
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-        ]);
+        let result = count_in_dirs(
+            &[Dir::DOWN_RIGHT],
+            &[
+                vec!['X', 'M', 'A', 'S'],
+                vec!['X', 'M', 'A', 'S'],
+                vec!['X', 'M', 'A', 'S'],
+                vec!['X', 'M', 'A', 'S'],
+                vec!['X', 'M', 'A', 'S'],
+            ],
+        );
         assert_eq!(result, 2);
     }
 
     #[test]
     fn count_diagonal_l_to_r_rev_test() {
-        let result = count_diagonal_l_to_r_rev(&vec![
-            vec![XMAS::S, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::S, XMAS::A, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::A, XMAS::M, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::M, XMAS::X],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::X],
-        ]);
+        let result = count_in_dirs(
+            &[Dir::DOWN_RIGHT.rev()],
+            &[
+                vec!['S', 'M', 'A', 'S'],
+                vec!['S', 'A', 'A', 'S'],
+                vec!['X', 'A', 'M', 'S'],
+                vec!['X', 'M', 'M', 'X'],
+                vec!['X', 'M', 'A', 'X'],
+            ],
+        );
         assert_eq!(result, 2);
     }
 
     #[test]
     fn count_diagonal_r_to_l_test() {
-        let result = count_diagonal_r_to_l(&vec![
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::X],
-            vec![XMAS::X, XMAS::M, XMAS::M, XMAS::X],
-            vec![XMAS::X, XMAS::A, XMAS::M, XMAS::S],
-            vec![XMAS::S, XMAS::A, XMAS::A, XMAS::S],
-            vec![XMAS::S, XMAS::M, XMAS::A, XMAS::S],
-        ]);
+        let result = count_in_dirs(
+            &[Dir::DOWN_LEFT],
+            &[
+                vec!['X', 'M', 'A', 'X'],
+                vec!['X', 'M', 'M', 'X'],
+                vec!['X', 'A', 'M', 'S'],
+                vec!['S', 'A', 'A', 'S'],
+                vec!['S', 'M', 'A', 'S'],
+            ],
+        );
         assert_eq!(result, 2);
     }
 
     #[test]
     fn count_diagonal_r_to_l_rev_test() {
-        let result = count_diagonal_r_to_l_rev(&vec![
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-            vec![XMAS::X, XMAS::M, XMAS::A, XMAS::S],
-        ]);
+        let result = count_in_dirs(
+            &[Dir::DOWN_LEFT.rev()],
+            &[
+                vec!['X', 'M', 'A', 'S'],
+                vec!['X', 'M', 'A', 'S'],
+                vec!['X', 'M', 'A', 'S'],
+                vec!['X', 'M', 'A', 'S'],
+                vec!['X', 'M', 'A', 'S'],
+            ],
+        );
         assert_eq!(result, 2);
     }
 
     #[test]
     fn count_mas_top_test() {
-        let result = count_mas_top(&vec![
-            vec![XMAS::M, XMAS::M, XMAS::M],
-            vec![XMAS::X, XMAS::A, XMAS::A],
-            vec![XMAS::S, XMAS::M, XMAS::S],
-        ]);
+        let result = count_exact(
+            "M.M\n.A.\nS.S",
+            &[
+                vec!['M', 'M', 'M'],
+                vec!['X', 'A', 'A'],
+                vec!['S', 'M', 'S'],
+            ],
+        );
         assert_eq!(result, 1);
     }
 
     #[test]
     fn count_mas_left_test() {
-        let result = count_mas_left(&vec![
-            vec![XMAS::M, XMAS::M, XMAS::S],
-            vec![XMAS::X, XMAS::A, XMAS::A],
-            vec![XMAS::M, XMAS::M, XMAS::S],
-        ]);
+        let result = count_exact(
+            "M.S\n.A.\nM.S",
+            &[
+                vec!['M', 'M', 'S'],
+                vec!['X', 'A', 'A'],
+                vec!['M', 'M', 'S'],
+            ],
+        );
         assert_eq!(result, 1);
     }
 
     #[test]
     fn count_mas_right_test() {
-        let result = count_mas_right(&vec![
-            vec![XMAS::S, XMAS::M, XMAS::M],
-            vec![XMAS::X, XMAS::A, XMAS::A],
-            vec![XMAS::S, XMAS::M, XMAS::M],
-        ]);
+        let result = count_exact(
+            "S.M\n.A.\nS.M",
+            &[
+                vec!['S', 'M', 'M'],
+                vec!['X', 'A', 'A'],
+                vec!['S', 'M', 'M'],
+            ],
+        );
         assert_eq!(result, 1);
     }
 
     #[test]
     fn count_mas_bottom_test() {
-        let result = count_mas_bottom(&vec![
-            vec![XMAS::S, XMAS::M, XMAS::S],
-            vec![XMAS::X, XMAS::A, XMAS::A],
-            vec![XMAS::M, XMAS::M, XMAS::M],
-        ]);
+        let result = count_exact(
+            "S.S\n.A.\nM.M",
+            &[
+                vec!['S', 'M', 'S'],
+                vec!['X', 'A', 'A'],
+                vec!['M', 'M', 'M'],
+            ],
+        );
         assert_eq!(result, 1);
     }
 
@@ -470,4 +732,207 @@ MXMXAXMASX",
         );
         assert_eq!(result, 9);
     }
+
+    #[test]
+    fn search_words_test() {
+        let table = parse_text(
+            "CATS
+OXDO
+WDOG
+SAGX",
+        );
+        let result = search_words(&table, &["CAT", "DOG", "COW"], &directions(false, false));
+        assert_eq!(
+            result,
+            vec![
+                WordMatch {
+                    word: 0,
+                    start: Pos { row: 0, col: 0 },
+                    dir: Dir::RIGHT,
+                },
+                WordMatch {
+                    word: 2,
+                    start: Pos { row: 0, col: 0 },
+                    dir: Dir::DOWN,
+                },
+                WordMatch {
+                    word: 1,
+                    start: Pos { row: 1, col: 2 },
+                    dir: Dir::DOWN,
+                },
+                WordMatch {
+                    word: 1,
+                    start: Pos { row: 2, col: 1 },
+                    dir: Dir::RIGHT,
+                },
+            ]
+        );
+        assert_eq!(
+            search_words(&table, &["GOD"], &directions(false, true)).len(),
+            2
+        );
+        assert_eq!(
+            search_words(&table, &["CXOX"], &directions(true, false)).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn template_variants_test() {
+        let x_mas = Template::parse("M.S\n.A.\nM.S");
+        assert_eq!(x_mas.variants().len(), 4);
+        assert_eq!(x_mas.rotate().rotate().rotate().rotate(), x_mas);
+
+        let plus = Template::parse(".X.\nXXX\n.X.");
+        assert_eq!(plus.variants(), vec![plus.clone()]);
+
+        let corner = Template::parse("AB\nC.");
+        assert_eq!(corner.variants().len(), 8);
+
+        let ragged = Template::parse(".X.\nXXX\n.X");
+        assert_eq!(ragged, plus);
+        assert_eq!(ragged.variants(), vec![plus]);
+    }
+
+    #[test]
+    fn match_exact_bounds_test() {
+        let table = parse_text("XX\nXX");
+        assert_eq!(
+            match_exact(&table, &Template::parse("X.")),
+            vec![Pos { row: 0, col: 0 }, Pos { row: 1, col: 0 }]
+        );
+        assert_eq!(
+            match_exact(&table, &Template::parse("X\n.")),
+            vec![Pos { row: 0, col: 0 }, Pos { row: 0, col: 1 }]
+        );
+        assert!(match_exact(&table, &Template::parse("...")).is_empty());
+    }
+
+    #[test]
+    fn match_template_plus_test() {
+        let table = parse_text(
+            "SMSX
+MAMX
+SMSX",
+        );
+        let plus = Template::parse(".M.\nMAM\n.M.");
+        let result = match_template(&table, &plus);
+        assert_eq!(
+            result,
+            vec![TemplateMatch {
+                variant: 0,
+                start: Pos { row: 0, col: 0 },
+            }]
+        );
+        let x_mas = Template::parse("S.S\n.A.\nS.S");
+        assert_eq!(match_template(&table, &x_mas).len(), 1);
+    }
+
+    #[test]
+    fn count_words_test() {
+        let table = parse_text(
+            "MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX",
+        );
+        let words = ["XMAS", "MAS", "AM", "SAS", "X"];
+        let result = count_words(&table, &words, true, true);
+        assert_eq!(result[0], 18);
+        for (i, word) in words.iter().enumerate() {
+            let expected = search_words(&table, &[word], &directions(true, true)).len();
+            assert_eq!(result[i], expected, "{}", word);
+        }
+        let expected = search_words(&table, &["MAS"], &directions(false, false)).len();
+        assert_eq!(count_words(&table, &["MAS"], false, false), vec![expected]);
+    }
+
+    #[test]
+    fn aho_corasick_overlapping_test() {
+        let patterns = ["he", "she", "his", "hers"]
+            .iter()
+            .map(|w| w.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let automaton = AhoCorasick::new(&patterns);
+        let mut counts = vec![0; patterns.len()];
+        automaton.count(&"ushershis".chars().collect::<Vec<_>>(), &mut counts);
+        assert_eq!(counts, vec![1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn render_xmas_test() {
+        let result = render_xmas(
+            "MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX",
+            false,
+        );
+        assert_eq!(
+            result,
+            "....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX
+"
+        );
+    }
+
+    #[test]
+    fn render_mas_test() {
+        let result = render_mas(
+            "MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX",
+            false,
+        );
+        assert_eq!(
+            result,
+            ".M.S......
+..A..MSMS.
+.M.S.MAA..
+..A.ASMSM.
+.M.S.M....
+..........
+S.S.S.S.S.
+.A.A.A.A..
+M.M.M.M.M.
+..........
+"
+        );
+    }
+
+    #[test]
+    fn render_colour_test() {
+        let result = render_xmas("XMAS\nABCD", true);
+        assert_eq!(
+            result,
+            "\x1b[31mX\x1b[0m\x1b[31mM\x1b[0m\x1b[31mA\x1b[0m\x1b[31mS\x1b[0m\n....\n"
+        );
+    }
 }
@@ -1,8 +1,12 @@
 use std::collections::HashSet;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, prelude::*, BufReader};
 
 fn main() -> io::Result<()> {
+    if std::env::args().nth(1).as_deref() == Some("explain") {
+        return explain().map(|report| print!("{}", report));
+    }
     part1().map(|count| println!("{}", count))?;
     part2().map(|count| println!("{}", count))
 }
@@ -35,11 +39,26 @@ fn part2() -> io::Result<u32> {
     Ok(sum)
 }
 
+fn explain() -> io::Result<String> {
+    let file = File::open("input.txt")?;
+    let reader = BufReader::new(file);
+
+    let text = reader
+        .lines()
+        .map(|x| x.unwrap())
+        .collect::<Vec<String>>()
+        .join("\n");
+    let report = explain_incorrect(text.trim());
+
+    Ok(report)
+}
+
 fn sum_correct_middle(text: &str) -> u32 {
     let (rules, updates) = parse_text(text);
+    let lookup = rules.iter().copied().collect();
     updates
         .iter()
-        .filter(|x| is_correct(x, &rules))
+        .filter(|x| is_correct(x, &lookup))
         .map(|x| {
             let mid = (x.len() - 1) / 2;
             x[mid]
@@ -49,9 +68,10 @@ fn sum_correct_middle(text: &str) -> u32 {
 
 fn sum_incorrect_middle_after_correction(text: &str) -> u32 {
     let (rules, updates) = parse_text(text);
+    let lookup = rules.iter().copied().collect();
     updates
         .iter()
-        .filter(|x| !is_correct(x, &rules))
+        .filter(|x| !is_correct(x, &lookup))
         .map(|x| {
             let mid = (x.len() - 1) / 2;
             let corrected = correct_by_rules(x, &rules);
@@ -60,8 +80,81 @@ fn sum_incorrect_middle_after_correction(text: &str) -> u32 {
         .sum()
 }
 
-fn correct_by_rules(update: &Vec<u32>, rules: &Vec<(u32, u32)>) -> Vec<u32> {
-    let mut corrected = update.clone();
+fn explain_incorrect(text: &str) -> String {
+    let (rules, updates) = parse_text(text);
+    let lookup = rules.iter().copied().collect();
+    let mut report = String::new();
+    for update in updates.iter() {
+        let violations = find_violations(update, &lookup);
+        if violations.is_empty() {
+            continue;
+        }
+        let pages = update
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        report += format!("{}\n", pages).as_str();
+        for violation in violations {
+            report += format!("  {}\n", violation).as_str();
+        }
+        let correction = correct_with_moves(update, &rules);
+        for m in correction.moves {
+            report += format!("  {}\n", m).as_str();
+        }
+        let corrected = correction
+            .order
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        report += format!("  => {}\n", corrected).as_str();
+    }
+    report
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Violation {
+    rule: (u32, u32),
+    before_pos: usize,
+    after_pos: usize,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}|{} violated at positions {} and {}",
+            self.rule.0, self.rule.1, self.before_pos, self.after_pos
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Move {
+    page: u32,
+    from: usize,
+    to: usize,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {} from position {} to {}",
+            self.page, self.from, self.to
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Correction {
+    order: Vec<u32>,
+    moves: Vec<Move>,
+}
+
+fn correct_by_rules(update: &[u32], rules: &[(u32, u32)]) -> Vec<u32> {
+    let mut corrected = update.to_vec();
     let mut found = true;
     while found {
         found = false;
@@ -84,20 +177,85 @@ fn correct_by_rules(update: &Vec<u32>, rules: &Vec<(u32, u32)>) -> Vec<u32> {
     corrected
 }
 
-fn is_correct(update: &Vec<u32>, rules: &Vec<(u32, u32)>) -> bool {
-    for i in 0..update.len() - 1 {
-        let page = update[i];
-        let filtered_rules: HashSet<u32> = rules
-            .iter()
-            .filter_map(|(a, b)| if *b == page { Some(*a) } else { None })
-            .collect();
-        for k in i..update.len() {
-            if filtered_rules.contains(&update[k]) {
-                return false;
+fn correct_with_moves(update: &[u32], rules: &[(u32, u32)]) -> Correction {
+    let order = correct_by_rules(update, rules);
+    let target_idx = update
+        .iter()
+        .map(|page| order.iter().position(|x| x == page).unwrap())
+        .collect::<Vec<_>>();
+
+    // Pages on a longest increasing run of target positions stay put,
+    // every other page needs exactly one move.
+    let mut run_len = vec![1; update.len()];
+    let mut run_prev = vec![None; update.len()];
+    for i in 0..update.len() {
+        for k in 0..i {
+            if target_idx[k] < target_idx[i] && run_len[k] + 1 > run_len[i] {
+                run_len[i] = run_len[k] + 1;
+                run_prev[i] = Some(k);
             }
         }
     }
-    true
+    let mut kept: HashSet<u32> = HashSet::new();
+    let mut last = (0..update.len()).max_by_key(|&i| run_len[i]);
+    while let Some(i) = last {
+        kept.insert(update[i]);
+        last = run_prev[i];
+    }
+
+    let mut current = update.to_vec();
+    let mut moves = Vec::new();
+    for (t, page) in order.iter().enumerate() {
+        if kept.contains(page) {
+            continue;
+        }
+        let from = current.iter().position(|x| x == page).unwrap();
+        current.remove(from);
+        let to = if t == 0 {
+            0
+        } else {
+            current.iter().position(|x| *x == order[t - 1]).unwrap() + 1
+        };
+        current.insert(to, *page);
+        moves.push(Move {
+            page: *page,
+            from,
+            to,
+        });
+    }
+    debug_assert_eq!(current, order);
+
+    Correction { order, moves }
+}
+
+fn is_correct(update: &[u32], rules: &HashSet<(u32, u32)>) -> bool {
+    first_violation(update, rules).is_none()
+}
+
+fn first_violation(update: &[u32], rules: &HashSet<(u32, u32)>) -> Option<Violation> {
+    violations(update, rules).next()
+}
+
+fn find_violations(update: &[u32], rules: &HashSet<(u32, u32)>) -> Vec<Violation> {
+    violations(update, rules).collect()
+}
+
+fn violations<'a>(
+    update: &'a [u32],
+    rules: &'a HashSet<(u32, u32)>,
+) -> impl Iterator<Item = Violation> + 'a {
+    update.iter().enumerate().flat_map(move |(i, page)| {
+        update
+            .iter()
+            .enumerate()
+            .skip(i + 1)
+            .filter(move |(_, later)| rules.contains(&(**later, *page)))
+            .map(move |(k, later)| Violation {
+                rule: (*later, *page),
+                before_pos: k,
+                after_pos: i,
+            })
+    })
 }
 
 fn parse_text(text: &str) -> (Vec<(u32, u32)>, Vec<Vec<u32>>) {
@@ -131,6 +289,10 @@ fn parse_text(text: &str) -> (Vec<(u32, u32)>, Vec<Vec<u32>>) {
 mod tests {
     use super::*;
 
+    fn rule_set(rules: &[(u32, u32)]) -> HashSet<(u32, u32)> {
+        rules.iter().copied().collect()
+    }
+
     #[test]
     fn sum_correct_middle_test() {
         let result = sum_correct_middle(
@@ -200,4 +362,107 @@ mod tests {
         );
         assert_eq!(result, 123);
     }
+
+    const EXAMPLE: &str = "47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47";
+
+    #[test]
+    fn first_violation_test() {
+        let (rules, _) = parse_text(EXAMPLE);
+        let result = first_violation(&[75, 97, 47, 61, 53], &rule_set(&rules));
+        assert_eq!(
+            result,
+            Some(Violation {
+                rule: (97, 75),
+                before_pos: 1,
+                after_pos: 0,
+            })
+        );
+        assert_eq!(
+            result.unwrap().to_string(),
+            "97|75 violated at positions 1 and 0"
+        );
+        assert_eq!(
+            first_violation(&[75, 47, 61, 53, 29], &rule_set(&rules)),
+            None
+        );
+    }
+
+    #[test]
+    fn find_violations_test() {
+        let (rules, _) = parse_text(EXAMPLE);
+        let result = find_violations(&[61, 13, 29], &rule_set(&rules));
+        assert_eq!(
+            result,
+            vec![Violation {
+                rule: (29, 13),
+                before_pos: 2,
+                after_pos: 1,
+            }]
+        );
+        assert_eq!(
+            find_violations(&[97, 13, 75, 29, 47], &rule_set(&rules)).len(),
+            4
+        );
+    }
+
+    #[test]
+    fn correct_with_moves_test() {
+        let (rules, _) = parse_text(EXAMPLE);
+        let result = correct_with_moves(&[97, 13, 75, 29, 47], &rules);
+        assert_eq!(result.order, vec![97, 75, 47, 29, 13]);
+        assert_eq!(result.moves.len(), 2);
+
+        let result = correct_with_moves(&[75, 97, 47, 61, 53], &rules);
+        assert_eq!(result.order, vec![97, 75, 47, 61, 53]);
+        assert_eq!(
+            result.moves,
+            vec![Move {
+                page: 97,
+                from: 1,
+                to: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn correct_with_moves_replay_test() {
+        let (rules, updates) = parse_text(EXAMPLE);
+        for update in updates {
+            let result = correct_with_moves(&update, &rules);
+            let mut current = update.clone();
+            for m in &result.moves {
+                assert_eq!(current.remove(m.from), m.page);
+                current.insert(m.to, m.page);
+            }
+            assert_eq!(current, result.order);
+            assert!(is_correct(&current, &rule_set(&rules)));
+        }
+    }
 }
@@ -1,11 +1,34 @@
-use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use combinatorics::pairs;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
 use std::io::{self, prelude::*, BufReader};
 
 fn main() -> io::Result<()> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let harmonics = if args.iter().any(|x| x == "every-point") {
+        Harmonics::EveryPoint
+    } else {
+        Harmonics::Multiples
+    };
+    let model = if args.iter().any(|x| x == "--pairs") {
+        None
+    } else {
+        Some(harmonics)
+    };
+    match args.get(1).map(|x| x.as_str()) {
+        Some("breakdown") => return breakdown(model).map(|report| print!("{}", report)),
+        Some("render") => {
+            let frequency = args
+                .iter()
+                .position(|x| x == "--frequency")
+                .and_then(|i| args.get(i + 1))
+                .and_then(|x| x.chars().next());
+            return render(frequency, model).map(|map| print!("{}", map));
+        }
+        _ => (),
+    }
     part1().map(|count| println!("{}", count))?;
-    part2().map(|count| println!("{}", count))
+    part2(harmonics).map(|count| println!("{}", count))
 }
 
 fn part1() -> io::Result<usize> {
@@ -22,7 +45,7 @@ fn part1() -> io::Result<usize> {
     Ok(count)
 }
 
-fn part2() -> io::Result<usize> {
+fn part2(harmonics: Harmonics) -> io::Result<usize> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
 
@@ -31,202 +54,204 @@ fn part2() -> io::Result<usize> {
         .map(|x| x.unwrap())
         .collect::<Vec<String>>()
         .join("\n");
-    let count = count_antinodes_with_harmonics(text.trim());
+    let count = count_antinodes_with_harmonics(text.trim(), harmonics);
 
     Ok(count)
 }
 
+fn breakdown(harmonics: Option<Harmonics>) -> io::Result<String> {
+    let file = File::open("input.txt")?;
+    let reader = BufReader::new(file);
+
+    let text = reader
+        .lines()
+        .map(|x| x.unwrap())
+        .collect::<Vec<String>>()
+        .join("\n");
+    let report = antinode_breakdown(text.trim(), harmonics);
+
+    Ok(report)
+}
+
+fn render(frequency: Option<char>, harmonics: Option<Harmonics>) -> io::Result<String> {
+    let file = File::open("input.txt")?;
+    let reader = BufReader::new(file);
+
+    let text = reader
+        .lines()
+        .map(|x| x.unwrap())
+        .collect::<Vec<String>>()
+        .join("\n");
+    let map = render_antinodes(text.trim(), frequency, harmonics);
+
+    Ok(map)
+}
+
 fn count_antinodes(text: &str) -> usize {
-    let (width, height, map) = parse_text(text);
-    let mut antinodes = HashSet::new();
-    for list in map {
-        for pair in list.iter().combinations(2) {
-            let p0 = pair.get(0).unwrap();
-            let p1 = pair.get(1).unwrap();
-            for x in get_antinode(width, height, p0, p1) {
-                antinodes.insert(x);
-            }
-        }
-    }
-    antinodes.len()
+    let map = parse_text(text);
+    map.all_antinodes(None).len()
 }
 
 fn get_antinode(width: i32, height: i32, p0: &Pos, p1: &Pos) -> Vec<Pos> {
-    let ((row0, col0), (row1, col1)) = if p0.row < p1.row {
-        let diff_row = p1.row - p0.row;
-        if p0.col < p1.col {
-            let diff_col = p1.col - p0.col;
-            (
-                (p0.row - diff_row, p0.col - diff_col),
-                (p1.row + diff_row, p1.col + diff_col),
-            )
-        } else {
-            let diff_col = p0.col - p1.col;
-            (
-                (p0.row - diff_row, p0.col + diff_col),
-                (p1.row + diff_row, p1.col - diff_col),
-            )
-        }
-    } else {
-        let diff_row = p0.row - p1.row;
-        if p0.col < p1.col {
-            let diff_col = p1.col - p0.col;
-            (
-                (p0.row + diff_row, p0.col - diff_col),
-                (p1.row - diff_row, p1.col + diff_col),
-            )
-        } else {
-            let diff_col = p0.col - p1.col;
-            (
-                (p0.row + diff_row, p0.col + diff_col),
-                (p1.row - diff_row, p1.col - diff_col),
-            )
+    let (d_row, d_col) = (p1.row - p0.row, p1.col - p0.col);
+    [
+        Pos {
+            row: p0.row - d_row,
+            col: p0.col - d_col,
+        },
+        Pos {
+            row: p1.row + d_row,
+            col: p1.col + d_col,
+        },
+    ]
+    .into_iter()
+    .filter(|p| p.is_inside(width, height))
+    .collect()
+}
+
+fn count_antinodes_with_harmonics(text: &str, harmonics: Harmonics) -> usize {
+    let map = parse_text(text);
+    map.all_antinodes(Some(harmonics)).len()
+}
+
+fn overlaps(map: &AntennaMap, harmonics: Option<Harmonics>) -> BTreeMap<(char, char), usize> {
+    let by_frequency = map
+        .frequencies()
+        .into_iter()
+        .map(|ch| (ch, map.antinodes(ch, harmonics)))
+        .collect::<Vec<_>>();
+    let mut res = BTreeMap::new();
+    for ((a, set_a), (b, set_b)) in pairs(&by_frequency) {
+        let shared = set_a.intersection(set_b).count();
+        if shared > 0 {
+            res.insert((*a, *b), shared);
         }
-    };
-    let mut res = Vec::new();
-    if col0 >= 0 && col0 < width && row0 >= 0 && row0 < height {
-        res.push(Pos {
-            row: row0,
-            col: col0,
-        });
-    }
-    if col1 >= 0 && col1 < width && row1 >= 0 && row1 < height {
-        res.push(Pos {
-            row: row1,
-            col: col1,
-        });
     }
     res
 }
 
-fn count_antinodes_with_harmonics(text: &str) -> usize {
-    let (width, height, map) = parse_text(text);
-    let mut antinodes = HashSet::new();
-    for list in map {
-        for pair in list.iter().combinations(2) {
-            let p0 = pair.get(0).unwrap();
-            let p1 = pair.get(1).unwrap();
-            get_antinode_from_left(&mut antinodes, width, height, p0, p1);
-            get_antinode_from_right(&mut antinodes, width, height, p0, p1);
-        }
+fn antinode_breakdown(text: &str, harmonics: Option<Harmonics>) -> String {
+    let map = parse_text(text);
+    let mut report = String::new();
+    for ch in map.frequencies() {
+        report += format!("{}: {}\n", ch, map.antinodes(ch, harmonics).len()).as_str();
     }
-    // print_antinodes(width, height, &antinodes);
-    antinodes.len()
+    for ((a, b), shared) in overlaps(&map, harmonics) {
+        report += format!("{} & {}: {} shared\n", a, b, shared).as_str();
+    }
+    report += format!("total: {}\n", map.all_antinodes(harmonics).len()).as_str();
+    report
 }
 
-fn print_antinodes(width: i32, height: i32, antinodes: &HashSet<Pos>) {
-    for r in 0..height {
-        for c in 0..width {
-            if antinodes.iter().contains(&Pos { row: r, col: c }) {
-                print!("#");
-            } else {
-                print!(".");
-            }
-        }
-        println!("");
+fn render_antinodes(text: &str, frequency: Option<char>, harmonics: Option<Harmonics>) -> String {
+    let mut map = parse_text(text);
+    if let Some(ch) = frequency {
+        map.antennas.retain(|x, _| *x == ch);
     }
-    // antinodes.iter().for_each(|x| println!("{:?}", x));
+    let antinodes = map.all_antinodes(harmonics);
+    render_map(&map, &antinodes)
 }
 
-fn get_antinode_from_left(
-    antinodes: &mut HashSet<Pos>,
-    width: i32,
-    height: i32,
-    p0: &Pos,
-    p1: &Pos,
-) {
-    antinodes.insert(*p0);
-    antinodes.insert(*p1);
-    // print_antinodes(width, height, &antinodes);
-    if p0.col == p1.col {
-        if p0.row < p1.row {
-            let diff_row = p1.row - p0.row;
-            let (row, col) = (p0.row - diff_row, p0.col);
-            if col >= 0 && col < width && row >= 0 && row < height {
-                let pp = Pos { row, col };
-                get_antinode_from_left(antinodes, width, height, &pp, p1);
-            }
-        } else if p1.row < p0.row {
-            let diff_row = p0.row - p1.row;
-            let (row, col) = (p1.row - diff_row, p0.col);
-            if col >= 0 && col < width && row >= 0 && row < height {
-                let pp = Pos { row, col };
-                get_antinode_from_left(antinodes, width, height, &pp, p1);
+fn render_map(map: &AntennaMap, antinodes: &HashSet<Pos>) -> String {
+    let antennas = map
+        .antennas
+        .iter()
+        .flat_map(|(ch, list)| list.iter().map(move |p| (*p, *ch)))
+        .collect::<HashMap<_, _>>();
+    let mut res = String::new();
+    for row in 0..map.height {
+        for col in 0..map.width {
+            let pos = Pos { row, col };
+            match antennas.get(&pos) {
+                Some(ch) => res.push(*ch),
+                None if antinodes.contains(&pos) => res.push('#'),
+                None => res.push('.'),
             }
         }
-    } else if p0.col < p1.col {
-        let diff_col = p1.col - p0.col;
-        let (row, col) = if p0.row < p1.row {
-            let diff_row = p1.row - p0.row;
-            (p0.row - diff_row, p0.col - diff_col)
-        } else {
-            let diff_row = p0.row - p1.row;
-            (p0.row + diff_row, p0.col - diff_col)
-        };
-        if col >= 0 && col < width && row >= 0 && row < height {
-            let pp1 = Pos { row, col };
-            // println!("l {:?} {:?}, {:?} {:?}", pp1, pp0, p0, p1);
-            get_antinode_from_left(antinodes, width, height, &pp1, p0);
-        } else {
-            // println!(
-            //     "else l {:?} height={height}, width={width}",
-            //     Pos { row, col }
-            // )
-        }
-    } else {
-        // println!("swap l {:?} {:?}", p0, p1);
-        get_antinode_from_left(antinodes, width, height, p1, p0);
+        res.push('\n');
     }
+    res
 }
 
-fn get_antinode_from_right(
-    antinodes: &mut HashSet<Pos>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AntennaMap {
+    antennas: HashMap<char, Vec<Pos>>,
     width: i32,
     height: i32,
-    p0: &Pos,
-    p1: &Pos,
-) {
-    antinodes.insert(*p0);
-    antinodes.insert(*p1);
-    // print_antinodes(width, height, &antinodes);
-    if p0.col == p1.col {
-        if p1.row > p0.row {
-            let diff_row = p1.row - p0.row;
-            let (row, col) = (p1.row + diff_row, p0.col);
-            if col >= 0 && col < width && row >= 0 && row < height {
-                let pp = Pos { row, col };
-                get_antinode_from_right(antinodes, width, height, &pp, p0);
-            }
-        } else if p0.row > p1.row {
-            let diff_row = p0.row - p1.row;
-            let (row, col) = (p0.row + diff_row, p0.col);
-            if col >= 0 && col < width && row >= 0 && row < height {
-                let pp = Pos { row, col };
-                get_antinode_from_right(antinodes, width, height, &pp, p0);
+}
+
+impl AntennaMap {
+    fn antennas(&self, frequency: char) -> &[Pos] {
+        self.antennas
+            .get(&frequency)
+            .map(|x| x.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn frequencies(&self) -> Vec<char> {
+        let mut res = self.antennas.keys().copied().collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    fn antinodes(&self, frequency: char, harmonics: Option<Harmonics>) -> HashSet<Pos> {
+        let mut antinodes = HashSet::new();
+        for (p0, p1) in pairs(self.antennas(frequency)) {
+            match harmonics {
+                None => antinodes.extend(get_antinode(self.width, self.height, p0, p1)),
+                Some(h) => antinodes.extend(get_line(self.width, self.height, p0, p1, h)),
             }
         }
-    } else if p0.col > p1.col {
-        let diff_col = p0.col - p1.col;
-        let (row, col) = if p0.row < p1.row {
-            let diff_row = p1.row - p0.row;
-            (p0.row - diff_row, p0.col + diff_col)
+        antinodes
+    }
+
+    fn all_antinodes(&self, harmonics: Option<Harmonics>) -> HashSet<Pos> {
+        self.frequencies()
+            .into_iter()
+            .flat_map(|ch| self.antinodes(ch, harmonics))
+            .collect()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Harmonics {
+    Multiples,
+    EveryPoint,
+}
+
+fn get_line(width: i32, height: i32, p0: &Pos, p1: &Pos, harmonics: Harmonics) -> Vec<Pos> {
+    let (mut d_row, mut d_col) = (p1.row - p0.row, p1.col - p0.col);
+    if harmonics == Harmonics::EveryPoint {
+        let g = gcd(d_row.abs(), d_col.abs());
+        d_row /= g;
+        d_col /= g;
+    }
+    let mut res = Vec::new();
+    for sign in [1, -1] {
+        let mut p = if sign == 1 {
+            *p0
         } else {
-            let diff_row = p0.row - p1.row;
-            (p0.row + diff_row, p0.col + diff_col)
+            Pos {
+                row: p0.row - d_row,
+                col: p0.col - d_col,
+            }
         };
-        if col >= 0 && col < width && row >= 0 && row < height {
-            let pp1 = Pos { row, col };
-            // println!("r {:?} {:?}, {:?} {:?}", pp1, pp0, p0, p1);
-            get_antinode_from_right(antinodes, width, height, p0, &pp1);
-        } else {
-            // println!(
-            //     "else r {:?} height={height}, width={width}",
-            //     Pos { row, col }
-            // )
+        while p.is_inside(width, height) {
+            res.push(p);
+            p = Pos {
+                row: p.row + sign * d_row,
+                col: p.col + sign * d_col,
+            };
         }
+    }
+    res
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a
     } else {
-        // println!("swap r {:?} {:?}", p0, p1);
-        get_antinode_from_right(antinodes, width, height, p1, p0);
+        gcd(b, a % b)
     }
 }
 
@@ -236,33 +261,31 @@ struct Pos {
     col: i32,
 }
 
-fn parse_text(text: &str) -> (i32, i32, Vec<Vec<Pos>>) {
-    let mut map: HashMap<char, Vec<Pos>> = HashMap::new();
+impl Pos {
+    fn is_inside(&self, width: i32, height: i32) -> bool {
+        self.col >= 0 && self.col < width && self.row >= 0 && self.row < height
+    }
+}
+
+fn parse_text(text: &str) -> AntennaMap {
+    let mut antennas: HashMap<char, Vec<Pos>> = HashMap::new();
     let mut row = 0;
     let mut col = 0;
     for line in text.lines() {
         col = 0;
         for char in line.chars() {
             if char != '.' {
-                match map.get(&char) {
-                    Some(list) => {
-                        let mut new_list: Vec<Pos> = vec![Pos { row, col }];
-                        new_list.extend(list);
-                        map.insert(char, new_list)
-                    }
-                    None => map.insert(char, vec![Pos { row, col }]),
-                };
+                antennas.entry(char).or_default().push(Pos { row, col });
             }
             col += 1;
         }
         row += 1;
     }
-    let positions = map
-        .into_iter()
-        .filter(|(_char, list)| list.len() > 1)
-        .map(|(_char, list)| list)
-        .collect();
-    (col, row, positions)
+    AntennaMap {
+        antennas,
+        width: col,
+        height: row,
+    }
 }
 
 #[cfg(test)]
@@ -335,6 +358,7 @@ mod tests {
 ..........
 ..........
 ..........",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 9);
     }
@@ -354,6 +378,7 @@ mod tests {
 .........A..
 ............
 ............",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 34);
     }
@@ -366,6 +391,7 @@ mod tests {
 .....0
 ......
 .....0",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 3);
     }
@@ -379,6 +405,7 @@ mod tests {
 .....0
 ......
 ......",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 3);
     }
@@ -389,6 +416,7 @@ mod tests {
             "......
 ....0.
 .....0",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 3);
     }
@@ -408,6 +436,7 @@ mod tests {
 .........A..
 ............
 ............",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 12);
     }
@@ -418,6 +447,7 @@ mod tests {
             "......
 ....0.
 ...0..",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 3);
     }
@@ -428,6 +458,7 @@ mod tests {
             ".....0
 ....0.
 ......",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 3);
     }
@@ -447,6 +478,7 @@ mod tests {
 ............
 ............
 ............",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 6);
     }
@@ -457,19 +489,117 @@ mod tests {
             "...0..
 ....0.
 ......",
+            Harmonics::Multiples,
         );
         assert_eq!(result, 3);
     }
 
     #[test]
     fn count_antinodes_with_harmonics_hr_test() {
-        let result = crate::count_antinodes_with_harmonics("0.0..");
+        let result = crate::count_antinodes_with_harmonics("0.0..", Harmonics::Multiples);
         assert_eq!(result, 3);
     }
 
     #[test]
     fn count_antinodes_with_harmonics_hl_test() {
-        let result = crate::count_antinodes_with_harmonics("...0.0");
+        let result = crate::count_antinodes_with_harmonics("...0.0", Harmonics::Multiples);
         assert_eq!(result, 3);
     }
+
+    #[test]
+    fn get_line_test() {
+        let p0 = Pos { row: 0, col: 0 };
+        let p1 = Pos { row: 2, col: 4 };
+        let mut result = get_line(5, 5, &p0, &p1, Harmonics::Multiples);
+        result.sort_by_key(|p| (p.row, p.col));
+        assert_eq!(result, vec![p0, p1]);
+
+        let mut result = get_line(5, 5, &p1, &p0, Harmonics::EveryPoint);
+        result.sort_by_key(|p| (p.row, p.col));
+        assert_eq!(result, vec![p0, Pos { row: 1, col: 2 }, p1]);
+    }
+
+    #[test]
+    fn count_antinodes_with_harmonics_every_point_test() {
+        let result = count_antinodes_with_harmonics("0.0..", Harmonics::EveryPoint);
+        assert_eq!(result, 5);
+    }
+
+    const EXAMPLE: &str = "............
+........0...
+.....0......
+.......0....
+....0.......
+......A.....
+............
+............
+........A...
+.........A..
+............
+............";
+
+    #[test]
+    fn antinode_breakdown_test() {
+        let result = antinode_breakdown(EXAMPLE, None);
+        assert_eq!(result, "0: 10\nA: 5\n0 & A: 1 shared\ntotal: 14\n");
+    }
+
+    #[test]
+    fn render_antinodes_test() {
+        let result = render_antinodes(EXAMPLE, None, None);
+        assert_eq!(
+            result,
+            "......#....#
+...#....0...
+....#0....#.
+..#....0....
+....0....#..
+.#....A.....
+...#........
+#......#....
+........A...
+.........A..
+..........#.
+..........#.
+"
+        );
+    }
+
+    #[test]
+    fn render_antinodes_single_frequency_test() {
+        let result = render_antinodes(
+            "T.........
+...T......
+.T........
+..........",
+            Some('T'),
+            Some(Harmonics::Multiples),
+        );
+        assert_eq!(
+            result,
+            "T....#....
+...T......
+.T....#...
+.........#
+"
+        );
+    }
+
+    #[test]
+    fn antenna_map_test() {
+        let map = parse_text(EXAMPLE);
+        assert_eq!((map.width, map.height), (12, 12));
+        assert_eq!(map.frequencies(), vec!['0', 'A']);
+        assert_eq!(
+            map.antennas('A'),
+            &[
+                Pos { row: 5, col: 6 },
+                Pos { row: 8, col: 8 },
+                Pos { row: 9, col: 9 },
+            ]
+        );
+        assert!(map.antennas('x').is_empty());
+        assert_eq!(map.antinodes('A', None).len(), 5);
+        assert_eq!(map.antinodes('A', Some(Harmonics::Multiples)).len(), 16);
+    }
 }